@@ -0,0 +1,308 @@
+use raylib::prelude::Vector3;
+use crate::camera::Camera;
+use crate::light::Light;
+use crate::material::vector3_to_color;
+use crate::transform::Transform;
+
+// Iteraciones de bisección al invertir la curva de Bézier (error < 2^-20)
+const BEZIER_ITERATIONS: u32 = 20;
+
+/// How a keyframe's value travels to the next one
+#[derive(Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    /// Holds the value until the next keyframe
+    Step,
+    Linear,
+    /// Timing curve through (0,0), (x1,y1), (x2,y2), (1,1), as in CSS easing
+    Bezier(f32, f32, f32, f32),
+}
+
+impl Interpolation {
+    /// Slow start and slow end
+    pub fn ease_in_out() -> Self {
+        Interpolation::Bezier(0.42, 0.0, 0.58, 1.0)
+    }
+
+    // Fracción del cambio de valor tras recorrer `t` del intervalo
+    fn apply(&self, t: f32) -> f32 {
+        match *self {
+            Interpolation::Step => 0.0,
+            Interpolation::Linear => t,
+            Interpolation::Bezier(x1, y1, x2, y2) => {
+                let bezier = |s: f32, p1: f32, p2: f32| {
+                    let inv = 1.0 - s;
+                    3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+                };
+                // x(s) es monótona si x1 y x2 están en [0, 1]: se busca s con x(s) = t
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..BEZIER_ITERATIONS {
+                    let mid = 0.5 * (low + high);
+                    if bezier(mid, x1, x2) < t {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                bezier(0.5 * (low + high), y1, y2)
+            }
+        }
+    }
+}
+
+/// Values that can be blended between keyframes
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vector3 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+#[derive(Clone)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    pub interpolation: Interpolation, // Curva hacia el siguiente keyframe
+}
+
+/// Keyframed value over time. Before the first key and after the last one
+/// the value is held, unless the track loops.
+#[derive(Clone)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+    looping: bool,
+}
+
+impl<T: Lerp> Track<T> {
+    pub fn new() -> Self {
+        Track {
+            keys: Vec::new(),
+            looping: false,
+        }
+    }
+
+    /// Adds a keyframe, keeping the keys sorted by time
+    pub fn key(mut self, time: f32, value: T, interpolation: Interpolation) -> Self {
+        let index = self.keys.partition_point(|key| key.time <= time);
+        self.keys.insert(index, Keyframe { time, value, interpolation });
+        self
+    }
+
+    /// Repeats the keys forever, from the first key's time to the last one's
+    pub fn looped(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let (first, last) = (self.keys.first()?, self.keys.last()?);
+        let span = last.time - first.time;
+        let time = if self.looping && span > 0.0 {
+            first.time + (time - first.time).rem_euclid(span)
+        } else {
+            time
+        };
+
+        // Primer keyframe posterior a `time`
+        let next = self.keys.partition_point(|key| key.time <= time);
+        if next == 0 {
+            return Some(first.value);
+        }
+        if next == self.keys.len() {
+            return Some(last.value);
+        }
+        let (from, to) = (&self.keys[next - 1], &self.keys[next]);
+        let t = (time - from.time) / (to.time - from.time);
+        Some(from.value.lerp(to.value, from.interpolation.apply(t)))
+    }
+}
+
+impl<T: Lerp> Default for Track<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves one scene object, identified by its index in the object list.
+/// Rotation (Euler angles) and scale are applied around `pivot`.
+#[derive(Clone)]
+pub struct ObjectAnimation {
+    pub index: usize,
+    pub pivot: Vector3,
+    pub translation: Track<Vector3>,
+    pub rotation: Track<Vector3>,
+    pub scale: Track<f32>,
+}
+
+impl ObjectAnimation {
+    pub fn new(index: usize, pivot: Vector3) -> Self {
+        ObjectAnimation {
+            index,
+            pivot,
+            translation: Track::new(),
+            rotation: Track::new(),
+            scale: Track::new(),
+        }
+    }
+
+    pub fn with_translation(mut self, track: Track<Vector3>) -> Self {
+        self.translation = track;
+        self
+    }
+
+    pub fn with_rotation(mut self, track: Track<Vector3>) -> Self {
+        self.rotation = track;
+        self
+    }
+
+    pub fn with_scale(mut self, track: Track<f32>) -> Self {
+        self.scale = track;
+        self
+    }
+
+    pub fn transform_at(&self, time: f32) -> Transform {
+        Transform::around_pivot(
+            self.pivot,
+            self.translation.sample(time).unwrap_or(Vector3::zero()),
+            self.rotation.sample(time).unwrap_or(Vector3::zero()),
+            self.scale.sample(time).unwrap_or(1.0),
+        )
+    }
+}
+
+/// Light tracks; empty tracks leave that property untouched
+#[derive(Clone, Default)]
+pub struct LightAnimation {
+    pub position: Track<Vector3>,
+    pub color: Track<Vector3>, // Componentes en [0, 1]
+    pub intensity: Track<f32>,
+}
+
+impl LightAnimation {
+    pub fn with_position(mut self, track: Track<Vector3>) -> Self {
+        self.position = track;
+        self
+    }
+
+    pub fn with_color(mut self, track: Track<Vector3>) -> Self {
+        self.color = track;
+        self
+    }
+
+    pub fn with_intensity(mut self, track: Track<f32>) -> Self {
+        self.intensity = track;
+        self
+    }
+
+    pub fn apply(&self, light: &mut Light, time: f32) {
+        if let Some(position) = self.position.sample(time) {
+            light.position = position;
+        }
+        if let Some(color) = self.color.sample(time) {
+            light.color = vector3_to_color(color);
+        }
+        if let Some(intensity) = self.intensity.sample(time) {
+            light.intensity = intensity;
+        }
+    }
+}
+
+/// Camera tracks for the eye position, the point looked at and the lens;
+/// empty tracks leave that property untouched
+#[derive(Clone, Default)]
+pub struct CameraAnimation {
+    pub eye: Track<Vector3>,
+    pub center: Track<Vector3>,
+    pub up: Track<Vector3>,
+    pub fov: Track<f32>,
+    pub aperture: Track<f32>,
+    pub focal_distance: Track<f32>,
+}
+
+impl CameraAnimation {
+    pub fn with_eye(mut self, track: Track<Vector3>) -> Self {
+        self.eye = track;
+        self
+    }
+
+    pub fn with_center(mut self, track: Track<Vector3>) -> Self {
+        self.center = track;
+        self
+    }
+
+    /// Loops every track over its own keys
+    pub fn looped(self) -> Self {
+        CameraAnimation {
+            eye: self.eye.looped(),
+            center: self.center.looped(),
+            up: self.up.looped(),
+            fov: self.fov.looped(),
+            aperture: self.aperture.looped(),
+            focal_distance: self.focal_distance.looped(),
+        }
+    }
+
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        if let Some(eye) = self.eye.sample(time) {
+            camera.eye = eye;
+        }
+        if let Some(center) = self.center.sample(time) {
+            camera.center = center;
+        }
+        if let Some(up) = self.up.sample(time) {
+            camera.up = up;
+        }
+        if let Some(fov) = self.fov.sample(time) {
+            camera.fov = fov;
+        }
+        if let Some(aperture) = self.aperture.sample(time) {
+            camera.aperture = aperture;
+        }
+        if let Some(focal_distance) = self.focal_distance.sample(time) {
+            camera.focal_distance = focal_distance;
+        }
+        camera.update_basis_vectors();
+    }
+}
+
+/// Every keyframed track in the scene, evaluated at the scene time
+#[derive(Clone, Default)]
+pub struct Animation {
+    pub objects: Vec<ObjectAnimation>,
+    pub light: Option<LightAnimation>,
+    pub camera: Option<CameraAnimation>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_object(mut self, object: ObjectAnimation) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    pub fn with_light(mut self, light: LightAnimation) -> Self {
+        self.light = Some(light);
+        self
+    }
+
+    pub fn with_camera(mut self, camera: CameraAnimation) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    /// Tracks of the object at `index`, if it is animated
+    pub fn object(&self, index: usize) -> Option<&ObjectAnimation> {
+        self.objects.iter().find(|object| object.index == index)
+    }
+}
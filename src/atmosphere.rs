@@ -0,0 +1,315 @@
+use raylib::prelude::Vector3;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use std::f32::consts::PI;
+use crate::light::Light;
+use crate::volume::HeterogeneousVolume;
+
+// Los rayos que escapan al cielo solo atraviesan niebla hasta esta distancia
+const MAX_FOG_DISTANCE: f32 = 40.0;
+// Estimaciones promediadas por rayo en los medios heterogéneos (menos ruido)
+const MEDIUM_SAMPLES: u32 = 4;
+// Pasos de la marcha en reflejos y refracciones, que no trazan sombras en el medio
+const SECONDARY_STEPS: u32 = 4;
+
+/// Exponential fog filling the whole scene. Density is `density` at
+/// `base_height` and decays with altitude at `height_falloff`; a falloff of
+/// zero gives uniform fog.
+#[derive(Clone)]
+pub struct Fog {
+    pub density: f32,
+    pub base_height: f32,
+    pub height_falloff: f32,
+    pub albedo: Vector3, // Fracción de luz dispersada por canal (color de la niebla)
+}
+
+impl Fog {
+    pub fn homogeneous(density: f32, albedo: Vector3) -> Self {
+        Fog {
+            density,
+            base_height: 0.0,
+            height_falloff: 0.0,
+            albedo,
+        }
+    }
+
+    /// Fog that settles in valleys: thick below `base_height`, thinning above it
+    pub fn height(density: f32, base_height: f32, height_falloff: f32, albedo: Vector3) -> Self {
+        Fog {
+            density,
+            base_height,
+            height_falloff,
+            albedo,
+        }
+    }
+
+    fn density_at(&self, point: &Vector3) -> f32 {
+        self.density * (-self.height_falloff * (point.y - self.base_height)).exp()
+    }
+}
+
+/// Box-shaped participating medium with its own density, e.g. steam or smoke
+#[derive(Clone)]
+pub struct FogVolume {
+    pub min: Vector3,
+    pub max: Vector3,
+    pub density: f32,
+    pub albedo: Vector3,
+}
+
+impl FogVolume {
+    pub fn new(center: Vector3, size: Vector3, density: f32, albedo: Vector3) -> Self {
+        FogVolume {
+            min: center - size * 0.5,
+            max: center + size * 0.5,
+            density,
+            albedo,
+        }
+    }
+
+    fn contains(&self, point: &Vector3) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+            && point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    fn ray_range(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, f32)> {
+        ray_box_range(ray_origin, ray_direction, &self.min, &self.max)
+    }
+}
+
+// Semilla derivada del propio rayo: el mismo rayo siempre da el mismo ruido,
+// así las imágenes se pueden reproducir exactamente
+fn ray_seed(ray_origin: &Vector3, ray_direction: &Vector3) -> u64 {
+    [ray_origin.x, ray_origin.y, ray_origin.z, ray_direction.x, ray_direction.y, ray_direction.z]
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, c| (hash ^ c.to_bits() as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+// Color de la luz escalado por su intensidad
+fn light_radiance(light: &Light) -> Vector3 {
+    Vector3::new(light.color.r as f32, light.color.g as f32, light.color.b as f32) / 255.0 * light.intensity
+}
+
+/// Stretch [t0, t1] of the ray (t0 >= 0) inside the axis-aligned box `min`..`max`
+pub fn ray_box_range(ray_origin: &Vector3, ray_direction: &Vector3, min: &Vector3, max: &Vector3) -> Option<(f32, f32)> {
+    let mut t0: f32 = 0.0;
+    let mut t1 = f32::INFINITY;
+    for (origin, direction, min, max) in [
+        (ray_origin.x, ray_direction.x, min.x, max.x),
+        (ray_origin.y, ray_direction.y, min.y, max.y),
+        (ray_origin.z, ray_direction.z, min.z, max.z),
+    ] {
+        if direction.abs() < 1e-8 {
+            if origin < min || origin > max {
+                return None;
+            }
+            continue;
+        }
+        let (near, far) = ((min - origin) / direction, (max - origin) / direction);
+        t0 = t0.max(near.min(far));
+        t1 = t1.min(near.max(far));
+    }
+    if t0 < t1 { Some((t0, t1)) } else { None }
+}
+
+/// Media between surfaces: global fog plus any number of bounded volumes.
+/// Rays are ray-marched with single scattering toward the light, so objects
+/// cast volumetric shadows through the medium. Heterogeneous media are
+/// estimated stochastically and composited in front of the surface first.
+#[derive(Clone)]
+pub struct Atmosphere {
+    pub fog: Option<Fog>,
+    pub volumes: Vec<FogVolume>,
+    pub media: Vec<HeterogeneousVolume>,
+    pub ambient: Vector3,  // Luz difusa del cielo que también ilumina el medio
+    pub anisotropy: f32,   // g de Henyey-Greenstein: > 0 dispersa hacia delante
+    pub steps: u32,
+}
+
+impl Atmosphere {
+    /// Clear air: no fog and no volumes
+    pub fn new() -> Self {
+        Atmosphere {
+            fog: None,
+            volumes: Vec::new(),
+            media: Vec::new(),
+            ambient: Vector3::new(0.3, 0.32, 0.38),
+            anisotropy: 0.3,
+            steps: 16,
+        }
+    }
+
+    pub fn with_fog(mut self, fog: Fog) -> Self {
+        self.fog = Some(fog);
+        self
+    }
+
+    pub fn with_volume(mut self, volume: FogVolume) -> Self {
+        self.volumes.push(volume);
+        self
+    }
+
+    pub fn with_medium(mut self, medium: HeterogeneousVolume) -> Self {
+        self.media.push(medium);
+        self
+    }
+
+    // Coeficiente de extinción total y color dispersado ponderado en un punto
+    fn medium_at(&self, point: &Vector3) -> (f32, Vector3) {
+        let mut extinction = 0.0;
+        let mut scattering = Vector3::zero();
+        if let Some(fog) = &self.fog {
+            let density = fog.density_at(point);
+            extinction += density;
+            scattering += fog.albedo * density;
+        }
+        for volume in &self.volumes {
+            if volume.contains(point) {
+                extinction += volume.density;
+                scattering += volume.albedo * volume.density;
+            }
+        }
+        (extinction, scattering)
+    }
+
+    // Tramo del rayo que hay que recorrer: todo si hay niebla, si no solo los volúmenes
+    fn march_range(&self, ray_origin: &Vector3, ray_direction: &Vector3, distance: f32) -> Option<(f32, f32)> {
+        let distance = distance.min(MAX_FOG_DISTANCE);
+        if self.fog.is_some() {
+            return Some((0.0, distance));
+        }
+        self.volumes
+            .iter()
+            .filter_map(|volume| volume.ray_range(ray_origin, ray_direction))
+            .map(|(t0, t1)| (t0, t1.min(distance)))
+            .filter(|(t0, t1)| t0 < t1)
+            .reduce(|(a0, a1), (b0, b1)| (a0.min(b0), a1.max(b1)))
+    }
+
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.anisotropy;
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    /// Attenuates `color`, seen `distance` away along the ray, and adds the
+    /// light scattered toward the viewer on the way. `visibility` returns how
+    /// much of the light reaches a point (0 = in shadow, 1 = lit).
+    pub fn apply(
+        &self,
+        ray_origin: &Vector3,
+        ray_direction: &Vector3,
+        distance: f32,
+        color: Vector3,
+        light: &Light,
+        visibility: impl Fn(&Vector3) -> f32,
+    ) -> Vector3 {
+        self.march(ray_origin, ray_direction, distance, color, light, Some(&visibility))
+    }
+
+    /// Cheaper `apply` for reflected and refracted rays: fewer steps and the
+    /// medium fully lit, so no shadow rays are traced
+    pub fn apply_secondary(&self, ray_origin: &Vector3, ray_direction: &Vector3, distance: f32, color: Vector3, light: &Light) -> Vector3 {
+        self.march(ray_origin, ray_direction, distance, color, light, None)
+    }
+
+    // Sin función de visibilidad el medio se toma entero iluminado y basta con menos pasos
+    fn march(
+        &self,
+        ray_origin: &Vector3,
+        ray_direction: &Vector3,
+        distance: f32,
+        color: Vector3,
+        light: &Light,
+        visibility: Option<&dyn Fn(&Vector3) -> f32>,
+    ) -> Vector3 {
+        let steps = if visibility.is_some() { self.steps } else { SECONDARY_STEPS };
+        let visibility = |point: &Vector3| visibility.map_or(1.0, |visibility| visibility(point));
+
+        // Medios heterogéneos, del más lejano al más cercano
+        let mut crossed: Vec<_> = self.media
+            .iter()
+            .filter_map(|medium| {
+                let (t0, t1) = medium.ray_range(ray_origin, ray_direction)?;
+                let t1 = t1.min(distance).min(MAX_FOG_DISTANCE);
+                (t0 < t1).then_some((medium, t0, t1))
+            })
+            .collect();
+        crossed.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let mut color = color;
+        for (medium, t0, t1) in crossed {
+            let (transmittance, scattered) = self.estimate_medium(medium, ray_origin, ray_direction, (t0, t1), light, &visibility);
+            color = color * transmittance + scattered;
+        }
+
+        let Some((t_start, t_end)) = self.march_range(ray_origin, ray_direction, distance) else {
+            return color;
+        };
+
+        let light_color = light_radiance(light);
+        let dt = (t_end - t_start) / steps as f32;
+        let mut transmittance = 1.0;
+        let mut scattered = Vector3::zero();
+
+        for step in 0..steps {
+            // Muestra en el centro de cada tramo
+            let t = t_start + (step as f32 + 0.5) * dt;
+            let point = *ray_origin + *ray_direction * t;
+            let (extinction, scattering) = self.medium_at(&point);
+            if extinction <= 0.0 {
+                continue;
+            }
+
+            let light_dir = (light.position - point).normalized();
+            let direct = light_color * (self.phase(ray_direction.dot(light_dir)) * visibility(&point));
+            scattered += (direct + self.ambient) * scattering * (transmittance * dt);
+            transmittance *= (-extinction * dt).exp();
+        }
+
+        color * transmittance + scattered
+    }
+
+    // Transmitancia por ratio tracking más dispersión simple en una colisión
+    // elegida por delta tracking; la luz que llega a la colisión también se
+    // atenúa a través del propio medio (autosombreado).
+    fn estimate_medium(
+        &self,
+        medium: &HeterogeneousVolume,
+        ray_origin: &Vector3,
+        ray_direction: &Vector3,
+        (t0, t1): (f32, f32),
+        light: &Light,
+        visibility: &impl Fn(&Vector3) -> f32,
+    ) -> (f32, Vector3) {
+        let light_color = light_radiance(light);
+        let mut rng = SmallRng::seed_from_u64(ray_seed(ray_origin, ray_direction));
+        let mut transmittance = 0.0;
+        let mut scattered = Vector3::zero();
+
+        for _ in 0..MEDIUM_SAMPLES {
+            transmittance += medium.transmittance(ray_origin, ray_direction, t0, t1, &mut rng);
+
+            if let Some(t) = medium.sample_collision(ray_origin, ray_direction, t0, t1, &mut rng) {
+                let point = *ray_origin + *ray_direction * t;
+                let light_dir = (light.position - point).normalized();
+                let light_distance = (light.position - point).length();
+                let self_shadow = medium
+                    .ray_range(&point, &light_dir)
+                    .map_or(1.0, |(a, b)| medium.transmittance(&point, &light_dir, a, b.min(light_distance), &mut rng));
+
+                let direct = light_color * (self.phase(ray_direction.dot(light_dir)) * visibility(&point) * self_shadow);
+                scattered += (direct + self.ambient) * medium.albedo;
+            }
+        }
+
+        let samples = MEDIUM_SAMPLES as f32;
+        (transmittance / samples, scattered / samples)
+    }
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,195 @@
+use raylib::prelude::Vector3;
+use std::fs;
+use std::path::Path;
+use crate::animation::{CameraAnimation, Interpolation, Lerp, Track};
+use crate::camera::{ApertureShape, Camera};
+
+/// Saved viewpoint: placement, field of view and lens of the camera
+#[derive(Clone)]
+pub struct Bookmark {
+    pub name: String,
+    pub eye: Vector3,
+    pub center: Vector3,
+    pub up: Vector3,
+    pub fov: f32,
+    pub aperture: f32,
+    pub focal_distance: f32,
+    pub aperture_shape: ApertureShape,
+}
+
+impl Bookmark {
+    pub fn capture(name: &str, camera: &Camera) -> Self {
+        Bookmark {
+            name: name.to_string(),
+            eye: camera.eye,
+            center: camera.center,
+            up: camera.up,
+            fov: camera.fov,
+            aperture: camera.aperture,
+            focal_distance: camera.focal_distance,
+            aperture_shape: camera.aperture_shape,
+        }
+    }
+
+    /// Jumps the camera straight to this viewpoint; lens values go through the
+    /// camera setters, so a hand-edited file is clamped like the keys
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.eye = self.eye;
+        camera.center = self.center;
+        camera.up = self.up;
+        camera.set_fov(self.fov);
+        camera.set_aperture(self.aperture);
+        camera.set_focal_distance(self.focal_distance);
+        camera.set_aperture_shape(self.aperture_shape);
+        camera.update_basis_vectors();
+    }
+
+    // Una línea por marcador: ranura (1 = F1), nombre, ojo, centro, up, fov,
+    // apertura, enfoque y forma
+    fn to_line(&self, slot: usize) -> String {
+        let shape = match self.aperture_shape {
+            ApertureShape::Circular => "circular".to_string(),
+            ApertureShape::Polygon { blades, rotation } => format!("polygon:{}:{}", blades, rotation),
+        };
+        let vectors = [self.eye, self.center, self.up]
+            .iter()
+            .map(|v| format!("{} {} {}", v.x, v.y, v.z))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{} {} {} {} {} {} {}", slot + 1, self.name, vectors, self.fov, self.aperture, self.focal_distance, shape)
+    }
+
+    fn parse(line: &str) -> Result<(usize, Self), String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 15 {
+            return Err(format!("Expected 15 fields, found {}", fields.len()));
+        }
+        let slot = fields[0].parse::<usize>().ok().filter(|&slot| slot > 0).ok_or_else(|| format!("Invalid slot '{}'", fields[0]))?;
+        let fields = &fields[1..];
+        let number = |i: usize| fields[i].parse::<f32>().map_err(|_| format!("Invalid number '{}'", fields[i]));
+        let vector = |i: usize| Ok::<_, String>(Vector3::new(number(i)?, number(i + 1)?, number(i + 2)?));
+
+        let aperture_shape = match fields[13].split(':').collect::<Vec<_>>()[..] {
+            ["circular"] => ApertureShape::Circular,
+            ["polygon", blades, rotation] => ApertureShape::Polygon {
+                blades: blades.parse().map_err(|_| format!("Invalid blade count '{}'", blades))?,
+                rotation: rotation.parse().map_err(|_| format!("Invalid rotation '{}'", rotation))?,
+            },
+            _ => return Err(format!("Invalid aperture shape '{}'", fields[13])),
+        };
+
+        Ok((slot - 1, Bookmark {
+            name: fields[0].to_string(),
+            eye: vector(1)?,
+            center: vector(4)?,
+            up: vector(7)?,
+            fov: number(10)?,
+            aperture: number(11)?,
+            focal_distance: number(12)?,
+            aperture_shape,
+        }))
+    }
+}
+
+/// Reads the bookmarks saved at `path`, indexed by slot (`None` for empty
+/// slots); a missing file just means none yet. Blank lines and lines
+/// starting with `#` are ignored.
+pub fn load(path: &Path) -> Result<Vec<Option<Bookmark>>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut bookmarks = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let (slot, bookmark) = Bookmark::parse(line).map_err(|e| format!("{}:{}: {}", path.display(), number + 1, e))?;
+        if slot >= bookmarks.len() {
+            bookmarks.resize(slot + 1, None);
+        }
+        bookmarks[slot] = Some(bookmark);
+    }
+    Ok(bookmarks)
+}
+
+pub fn save(path: &Path, bookmarks: &[Option<Bookmark>]) -> Result<(), String> {
+    let mut text = String::from("# ranura nombre ojo(x y z) centro(x y z) up(x y z) fov apertura enfoque forma\n");
+    for (slot, bookmark) in bookmarks.iter().enumerate() {
+        if let Some(bookmark) = bookmark {
+            text.push_str(&bookmark.to_line(slot));
+            text.push('\n');
+        }
+    }
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Smooth camera glide between viewpoints, played on its own clock so it
+/// runs the same whether scene time is paused or not
+pub struct Flythrough {
+    animation: CameraAnimation,
+    elapsed: f32,
+    duration: Option<f32>, // None: la visita se repite hasta que se detenga
+}
+
+impl Flythrough {
+    /// Glide from the current view to `bookmark` in `duration` seconds
+    pub fn to(camera: &Camera, bookmark: &Bookmark, duration: f32) -> Self {
+        let stops = [Bookmark::capture("", camera), bookmark.clone()];
+        Flythrough {
+            animation: Self::through(&stops, duration, 0.0),
+            elapsed: 0.0,
+            duration: Some(duration),
+        }
+    }
+
+    /// Presentation loop visiting every bookmark: `travel` seconds between
+    /// stops and `hold` seconds still at each one. `None` with fewer than two.
+    pub fn tour(bookmarks: &[Bookmark], travel: f32, hold: f32) -> Option<Self> {
+        let first = bookmarks.first().filter(|_| bookmarks.len() > 1)?;
+        let stops: Vec<Bookmark> = bookmarks.iter().chain(std::iter::once(first)).cloned().collect();
+        Some(Flythrough {
+            animation: Self::through(&stops, travel, hold).looped(),
+            elapsed: 0.0,
+            duration: None,
+        })
+    }
+
+    // Keyframes suavizados en cada parada; mantener es repetir el valor
+    fn through(stops: &[Bookmark], travel: f32, hold: f32) -> CameraAnimation {
+        let keys: Vec<(f32, &Bookmark)> = stops
+            .iter()
+            .enumerate()
+            .flat_map(|(i, stop)| {
+                let arrive = i as f32 * (travel + hold);
+                let leave = if i + 1 < stops.len() { arrive + hold } else { arrive };
+                [(arrive, stop), (leave, stop)]
+            })
+            .collect();
+
+        fn track<T: Lerp>(keys: &[(f32, &Bookmark)], value: impl Fn(&Bookmark) -> T) -> Track<T> {
+            keys.iter()
+                .fold(Track::new(), |track, (time, stop)| track.key(*time, value(stop), Interpolation::ease_in_out()))
+        }
+        CameraAnimation {
+            eye: track(&keys, |stop| stop.eye),
+            center: track(&keys, |stop| stop.center),
+            up: track(&keys, |stop| stop.up),
+            fov: track(&keys, |stop| stop.fov),
+            aperture: track(&keys, |stop| stop.aperture),
+            focal_distance: track(&keys, |stop| stop.focal_distance),
+        }
+    }
+
+    /// Advances the glide by `delta` seconds and places the camera; returns
+    /// false once a one-off glide has arrived
+    pub fn update(&mut self, camera: &mut Camera, delta: f32) -> bool {
+        self.elapsed += delta;
+        let time = match self.duration {
+            Some(duration) => self.elapsed.min(duration),
+            None => self.elapsed,
+        };
+        self.animation.apply(camera, time);
+        self.duration.is_none_or(|duration| self.elapsed < duration)
+    }
+}
@@ -1,12 +1,72 @@
 use raylib::prelude::*;
+use std::f32::consts::PI;
+
+/// Range of the vertical field of view accepted by `Camera::set_fov`, in radians
+pub const MIN_FOV: f32 = 0.1;
+pub const MAX_FOV: f32 = 3.0;
+
+/// Shape of the lens opening, which is also the shape of out-of-focus highlights (bokeh)
+#[derive(Clone, Copy, PartialEq)]
+pub enum ApertureShape {
+    Circular,
+    /// Regular polygon formed by `blades` diaphragm blades, rotated by `rotation` radians
+    Polygon { blades: u32, rotation: f32 },
+}
+
+impl ApertureShape {
+    /// Maps a uniform sample in [0, 1)² to a uniform point inside the unit-radius aperture
+    pub fn sample(&self, (u, v): (f32, f32)) -> (f32, f32) {
+        match *self {
+            ApertureShape::Circular => {
+                let radius = u.sqrt();
+                let angle = 2.0 * PI * v;
+                (radius * angle.cos(), radius * angle.sin())
+            }
+            ApertureShape::Polygon { blades, rotation } => {
+                // Todos los triángulos centro-lado tienen igual área: `u` elige uno
+                // y su parte fraccionaria se reutiliza dentro del triángulo
+                let blades = blades.max(3) as f32;
+                let sector = (u * blades).floor().min(blades - 1.0);
+                let u = u * blades - sector;
+                let corner = |i: f32| {
+                    let angle = rotation + 2.0 * PI * i / blades;
+                    (angle.cos(), angle.sin())
+                };
+                let (a, b) = (corner(sector), corner(sector + 1.0));
+                let s = u.sqrt();
+                (s * (a.0 + (b.0 - a.0) * v), s * (a.1 + (b.1 - a.1) * v))
+            }
+        }
+    }
+}
+
+/// How screen positions map to rays
+#[derive(Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Pinhole view with the camera's vertical `fov`
+    Perspective,
+    /// Parallel rays along `forward`; frames what the perspective view shows
+    /// at `center`, so zooming still changes the framing
+    Orthographic,
+    /// Equidistant fisheye: the circle inscribed in the image covers `fov` radians
+    Fisheye { fov: f32 },
+    /// Full 360° x 180° panorama (longitude across, latitude up), best at 2:1
+    Equirectangular,
+}
 
 /// A 3D camera that maintains its position and orientation in world space
+#[derive(Clone)]
 pub struct Camera {
     pub eye: Vector3,     // Camera position in world coordinates
     pub center: Vector3,  // Point the camera is looking at
     pub up: Vector3,      // Up direction (initially world up, gets orthonormalized)
     pub forward: Vector3, // Direction camera is facing (computed from eye->center)
     pub right: Vector3,   // Right direction (perpendicular to forward and up)
+    pub projection: Projection,
+    pub fov: f32,                  // Vertical field of view in radians (perspective)
+    pub aperture: f32,             // Lens radius in world units (0 = pinhole, everything sharp)
+    pub focal_distance: f32,       // Distance along `forward` to the plane in focus
+    pub aperture_shape: ApertureShape,
     changed: bool,
 }
 
@@ -20,6 +80,11 @@ impl Camera {
             up,
             forward: Vector3::zero(), // Will be computed
             right: Vector3::zero(),   // Will be computed
+            projection: Projection::Perspective,
+            fov: PI / 3.0,
+            aperture: 0.0,
+            focal_distance: 10.0,
+            aperture_shape: ApertureShape::Circular,
             changed: true,
         };
         // Compute the orthonormal basis vectors (forward, right, up)
@@ -27,6 +92,53 @@ impl Camera {
         camera
     }
 
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn with_fov(mut self, fov: f32) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    /// Thin lens of radius `aperture` focused at `focal_distance`
+    pub fn with_lens(mut self, aperture: f32, focal_distance: f32) -> Self {
+        self.aperture = aperture;
+        self.focal_distance = focal_distance;
+        self
+    }
+
+    pub fn with_aperture_shape(mut self, shape: ApertureShape) -> Self {
+        self.aperture_shape = shape;
+        self
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.changed = true;
+    }
+
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(MIN_FOV, MAX_FOV);
+        self.changed = true;
+    }
+
+    pub fn set_aperture(&mut self, aperture: f32) {
+        self.aperture = aperture.max(0.0);
+        self.changed = true;
+    }
+
+    pub fn set_aperture_shape(&mut self, shape: ApertureShape) {
+        self.aperture_shape = shape;
+        self.changed = true;
+    }
+
+    pub fn set_focal_distance(&mut self, distance: f32) {
+        self.focal_distance = distance.max(0.01);
+        self.changed = true;
+    }
+
     /// Recomputes the camera's orthonormal basis vectors from eye, center, and up
     pub fn update_basis_vectors(&mut self) {
         // Step 1: Calculate forward direction (from eye toward center)
@@ -86,6 +198,46 @@ impl Camera {
         self.update_basis_vectors();
     }
 
+    /// Free-fly movement: moves eye and center together by `right`, `up` and
+    /// `forward` units along the camera's own axes
+    pub fn fly(&mut self, right: f32, up: f32, forward: f32) {
+        let offset = self.right * right + self.up * up + self.forward * forward;
+        self.eye += offset;
+        self.center += offset;
+        self.update_basis_vectors();
+    }
+
+    /// Slides eye and center sideways (`right`) and vertically (`up`) in the view plane
+    pub fn pan(&mut self, right: f32, up: f32) {
+        self.fly(right, up, 0.0);
+    }
+
+    /// Makes `point` the center the camera looks at and orbits around, keeping the eye in place
+    pub fn recenter(&mut self, point: Vector3) {
+        if (point - self.eye).length() > 0.5 {
+            self.center = point;
+            self.update_basis_vectors();
+        }
+    }
+
+    /// Turns the view in place: positive `yaw` turns left and positive `pitch`
+    /// looks up, both around the camera's own axes. The center stays ahead at
+    /// the same distance, so orbiting afterwards starts from this very view.
+    pub fn look(&mut self, yaw: f32, pitch: f32) {
+        let distance = self.get_distance();
+        let forward = rotate_around(self.forward, self.up, yaw);
+        let right = forward.cross(self.up).normalized();
+        self.up = rotate_around(self.up, right, pitch);
+        self.center = self.eye + rotate_around(forward, right, pitch) * distance;
+        self.update_basis_vectors();
+    }
+
+    /// Tilts the horizon: positive `angle` turns the up vector toward the right
+    pub fn roll(&mut self, angle: f32) {
+        self.up = rotate_around(self.up, self.forward, angle);
+        self.update_basis_vectors();
+    }
+
     pub fn zoom(&mut self, amount: f32) {
         let forward = (self.center - self.eye).normalized();
         self.eye += forward * amount;
@@ -124,6 +276,76 @@ impl Camera {
         (self.eye - self.center).length()
     }
 
+    /// Camera partway from `self` (t = 0) to `other` (t = 1), e.g. during the shutter interval
+    pub fn lerp(&self, other: &Camera, t: f32) -> Camera {
+        let mut camera = other.clone();
+        camera.eye = self.eye + (other.eye - self.eye) * t;
+        camera.center = self.center + (other.center - self.center) * t;
+        camera.up = self.up + (other.up - self.up) * t;
+        camera.fov = self.fov + (other.fov - self.fov) * t;
+        camera.aperture = self.aperture + (other.aperture - self.aperture) * t;
+        camera.focal_distance = self.focal_distance + (other.focal_distance - self.focal_distance) * t;
+        camera.update_basis_vectors();
+        camera
+    }
+
+    /// World-space primary ray through a screen position, with `screen_x` and
+    /// `screen_y` in [-1, 1] (y up) and `lens_sample` in [0, 1)² picking the
+    /// point on the lens. `None` where the projection covers no direction,
+    /// e.g. outside the fisheye circle.
+    pub fn primary_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32, lens_sample: (f32, f32)) -> Option<(Vector3, Vector3)> {
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => {
+                let scale = (self.fov * 0.5).tan();
+                let direction = Vector3::new(screen_x * aspect_ratio * scale, screen_y * scale, -1.0).normalized();
+                (self.eye, self.basis_change(&direction))
+            }
+            Projection::Orthographic => {
+                let half_height = self.get_distance() * (self.fov * 0.5).tan();
+                let offset = self.right * (screen_x * aspect_ratio * half_height) + self.up * (screen_y * half_height);
+                (self.eye + offset, self.forward)
+            }
+            Projection::Fisheye { fov } => {
+                let (x, y) = (screen_x * aspect_ratio.max(1.0), screen_y / aspect_ratio.min(1.0));
+                let radius = (x * x + y * y).sqrt();
+                if radius > 1.0 {
+                    return None;
+                }
+                // Ángulo al eje proporcional a la distancia al centro de la imagen
+                let theta = radius * fov * 0.5;
+                let phi = y.atan2(x);
+                let direction = Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), -theta.cos());
+                (self.eye, self.basis_change(&direction))
+            }
+            Projection::Equirectangular => {
+                let longitude = screen_x * PI;
+                let latitude = screen_y * PI * 0.5;
+                let direction = Vector3::new(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    -latitude.cos() * longitude.cos(),
+                );
+                (self.eye, self.basis_change(&direction))
+            }
+        };
+        Some(self.through_lens(origin, direction, lens_sample))
+    }
+
+    // Lente delgada: el origen se desplaza por la apertura y el rayo apunta a
+    // donde el rayo sin lente corta el plano focal, que es lo único nítido.
+    // Los rayos que no van hacia delante (panorámicas) no pasan por la lente.
+    fn through_lens(&self, origin: Vector3, direction: Vector3, lens_sample: (f32, f32)) -> (Vector3, Vector3) {
+        let cos_axis = direction.dot(self.forward);
+        if self.aperture <= 0.0 || cos_axis <= 0.0 {
+            return (origin, direction);
+        }
+
+        let focus_point = origin + direction * (self.focal_distance / cos_axis);
+        let (lens_x, lens_y) = self.aperture_shape.sample(lens_sample);
+        let origin = origin + self.right * (lens_x * self.aperture) + self.up * (lens_y * self.aperture);
+        (origin, (focus_point - origin).normalized())
+    }
+
     pub fn is_changed(&mut self) -> bool {
         let changed = self.changed;
         self.changed = false;
@@ -160,3 +382,9 @@ impl Camera {
         // result will be -self.forward in world space
     }
 }
+
+// Rotación de `v` alrededor del eje unitario `axis` (fórmula de Rodrigues)
+fn rotate_around(v: Vector3, axis: Vector3, angle: f32) -> Vector3 {
+    let (sin, cos) = angle.sin_cos();
+    v * cos + axis.cross(v) * sin + axis * (axis.dot(v) * (1.0 - cos))
+}
@@ -0,0 +1,81 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+use crate::cylinder::{solve_quadratic, cylindrical_tangent, cylindrical_uv, AxisFrame};
+
+/// Capsule: a cylinder between two points with hemispherical ends
+#[derive(Clone)]
+pub struct Capsule {
+    pub radius: f32,
+    pub material: Material,
+    length: f32, // Distancia entre los centros de las semiesferas
+    frame: AxisFrame,
+}
+
+impl Capsule {
+    pub fn new(start: Vector3, end: Vector3, radius: f32, material: Material) -> Self {
+        let segment = end - start;
+        let length = segment.length();
+        let axis = if length > 1e-6 { segment } else { Vector3::new(0.0, 1.0, 0.0) };
+        Capsule {
+            radius,
+            material,
+            length,
+            frame: AxisFrame::new(start, axis),
+        }
+    }
+}
+
+impl RayIntersect for Capsule {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let o = self.frame.point_to_local(ray_origin);
+        let d = self.frame.direction_to_local(ray_direction);
+        let r2 = self.radius * self.radius;
+
+        // v recorre la cápsula completa, incluyendo las semiesferas
+        let total_length = self.length + 2.0 * self.radius;
+        let uv_at = |p: &Vector3| cylindrical_uv(p, (p.y + self.radius) / total_length);
+
+        let mut best: Option<(f32, Vector3, Vector3)> = None;
+        let mut consider = |t: f32, normal: Vector3, p: Vector3| {
+            if t > 0.0 && best.is_none_or(|(best_t, ..)| t < best_t) {
+                best = Some((t, normal, p));
+            }
+        };
+
+        // Cuerpo cilíndrico: x² + z² = r², con 0 <= y <= longitud
+        if let Some((t0, t1)) = solve_quadratic(d.x * d.x + d.z * d.z, 2.0 * (o.x * d.x + o.z * d.z), o.x * o.x + o.z * o.z - r2) {
+            for t in [t0, t1] {
+                let p = o + d * t;
+                if p.y >= 0.0 && p.y <= self.length {
+                    consider(t, Vector3::new(p.x, 0.0, p.z) / self.radius, p);
+                }
+            }
+        }
+
+        // Semiesferas en cada extremo (solo la mitad exterior al cuerpo)
+        for (center_y, outside) in [(0.0, -1.0f32), (self.length, 1.0f32)] {
+            let center = Vector3::new(0.0, center_y, 0.0);
+            let oc = o - center;
+            if let Some((t0, t1)) = solve_quadratic(d.dot(d), 2.0 * oc.dot(d), oc.dot(oc) - r2) {
+                for t in [t0, t1] {
+                    let p = o + d * t;
+                    if (p.y - center_y) * outside >= 0.0 {
+                        consider(t, (p - center) / self.radius, p);
+                    }
+                }
+            }
+        }
+
+        match best {
+            Some((t, normal, local_point)) => {
+                let point = *ray_origin + *ray_direction * t;
+                let (u, v) = uv_at(&local_point);
+                let tangent = self.frame.direction_to_world(&cylindrical_tangent(&local_point));
+                let normal = self.frame.direction_to_world(&normal);
+                Intersect::new(point, normal, t, self.material.clone(), u, v).with_tangent(tangent)
+            }
+            None => Intersect::empty(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+use crate::cylinder::{solve_quadratic, cylindrical_tangent, cylindrical_uv, AxisFrame};
+
+/// Cone with a capped circular base of `radius`, apex `height` units along `axis`
+#[derive(Clone)]
+pub struct Cone {
+    pub radius: f32,
+    pub height: f32,
+    pub material: Material,
+    frame: AxisFrame,
+}
+
+impl Cone {
+    pub fn new(base: Vector3, axis: Vector3, radius: f32, height: f32, material: Material) -> Self {
+        Cone {
+            radius,
+            height,
+            material,
+            frame: AxisFrame::new(base, axis),
+        }
+    }
+
+    /// Upright cone (apex towards +Y), e.g. tower roofs
+    pub fn vertical(base: Vector3, radius: f32, height: f32, material: Material) -> Self {
+        Cone::new(base, Vector3::new(0.0, 1.0, 0.0), radius, height, material)
+    }
+}
+
+impl RayIntersect for Cone {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let o = self.frame.point_to_local(ray_origin);
+        let d = self.frame.direction_to_local(ray_direction);
+
+        // Pendiente: el radio a la altura y es k·(h - y)
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let q = self.height - o.y;
+
+        let mut best: Option<(f32, Vector3, (f32, f32), Vector3)> = None;
+        let mut consider = |t: f32, normal: Vector3, uv: (f32, f32), tangent: Vector3| {
+            if t > 0.0 && best.is_none_or(|(best_t, ..)| t < best_t) {
+                best = Some((t, normal, uv, tangent));
+            }
+        };
+
+        // Superficie lateral: x² + z² = k²·(h - y)², con 0 <= y <= h
+        let a = d.x * d.x + d.z * d.z - k2 * d.y * d.y;
+        let b = 2.0 * (o.x * d.x + o.z * d.z) + 2.0 * k2 * q * d.y;
+        let c = o.x * o.x + o.z * o.z - k2 * q * q;
+        if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+            for t in [t0, t1] {
+                let p = o + d * t;
+                if p.y >= 0.0 && p.y <= self.height {
+                    let rho = (p.x * p.x + p.z * p.z).sqrt();
+                    // En el ápice la normal no está definida: usar el eje
+                    let normal = if rho > 1e-6 {
+                        Vector3::new(p.x, k * rho, p.z).normalized()
+                    } else {
+                        Vector3::new(0.0, 1.0, 0.0)
+                    };
+                    consider(t, normal, cylindrical_uv(&p, p.y / self.height), cylindrical_tangent(&p));
+                }
+            }
+        }
+
+        // Base circular (y = 0)
+        if d.y.abs() > 1e-6 {
+            let t = -o.y / d.y;
+            let p = o + d * t;
+            if p.x * p.x + p.z * p.z <= self.radius * self.radius {
+                let uv = (p.x / (2.0 * self.radius) + 0.5, p.z / (2.0 * self.radius) + 0.5);
+                consider(t, Vector3::new(0.0, -1.0, 0.0), uv, Vector3::new(1.0, 0.0, 0.0));
+            }
+        }
+
+        match best {
+            Some((t, normal, (u, v), tangent)) => {
+                let point = *ray_origin + *ray_direction * t;
+                let normal = self.frame.direction_to_world(&normal);
+                let tangent = self.frame.direction_to_world(&tangent);
+                Intersect::new(point, normal, t, self.material.clone(), u, v).with_tangent(tangent)
+            }
+            None => Intersect::empty(),
+        }
+    }
+}
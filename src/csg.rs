@@ -0,0 +1,134 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, Interval, RayIntersect};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    Union,        // A ∪ B
+    Intersection, // A ∩ B
+    Difference,   // A - B
+}
+
+impl CsgOperation {
+    fn contains(&self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => inside_left || inside_right,
+            CsgOperation::Intersection => inside_left && inside_right,
+            CsgOperation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+/// Boolean combination of two solids. Any `RayIntersect` works as a child,
+/// including other `Csg` nodes, so operations can be nested freely.
+///
+/// In a difference, the faces carved by `right` keep its material, so the
+/// cutter's material decides how the cut surfaces look.
+pub struct Csg {
+    pub operation: CsgOperation,
+    left: Box<dyn RayIntersect>,
+    right: Box<dyn RayIntersect>,
+}
+
+// Cruce de la frontera de un hijo a lo largo del rayo
+struct Crossing {
+    distance: f32,
+    is_left: bool,
+    entering: bool,
+    hit: Intersect,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    pub fn union(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+
+    // Convierte los intervalos de un hijo en cruces; devuelve si el origen ya está dentro
+    fn collect_crossings(intervals: Vec<Interval>, is_left: bool, crossings: &mut Vec<Crossing>) -> bool {
+        let mut starts_inside = false;
+        for interval in intervals {
+            if interval.enter.is_intersecting {
+                crossings.push(Crossing { distance: interval.enter.distance, is_left, entering: true, hit: interval.enter });
+            } else {
+                starts_inside = true;
+            }
+            if interval.exit.is_intersecting {
+                crossings.push(Crossing { distance: interval.exit.distance, is_left, entering: false, hit: interval.exit });
+            }
+        }
+        starts_inside
+    }
+}
+
+impl RayIntersect for Csg {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        // La superficie visible es la primera frontera real por delante del origen
+        for interval in self.ray_intervals(ray_origin, ray_direction) {
+            for boundary in [interval.enter, interval.exit] {
+                if boundary.is_intersecting && boundary.distance > 0.0 {
+                    return boundary;
+                }
+            }
+        }
+        Intersect::empty()
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<Interval> {
+        let mut crossings = Vec::new();
+        let mut inside_left = Self::collect_crossings(self.left.ray_intervals(ray_origin, ray_direction), true, &mut crossings);
+        let mut inside_right = Self::collect_crossings(self.right.ray_intervals(ray_origin, ray_direction), false, &mut crossings);
+        crossings.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        let mut inside = self.operation.contains(inside_left, inside_right);
+        let mut enter = if inside { Some(Interval::open_enter()) } else { None };
+        let mut intervals = Vec::new();
+
+        for crossing in crossings {
+            if crossing.is_left {
+                inside_left = crossing.entering;
+            } else {
+                inside_right = crossing.entering;
+            }
+
+            let now_inside = self.operation.contains(inside_left, inside_right);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            let mut hit = crossing.hit;
+            // Las caras talladas por el sustraendo apuntan hacia el hueco
+            if self.operation == CsgOperation::Difference && !crossing.is_left {
+                hit.normal = -hit.normal;
+            }
+
+            if inside {
+                enter = Some(hit);
+            } else {
+                let enter = enter.take().unwrap_or_else(Interval::open_enter);
+                intervals.push(Interval { enter, exit: hit });
+            }
+        }
+
+        if let Some(enter) = enter {
+            intervals.push(Interval { enter, exit: Interval::open_exit() });
+        }
+
+        intervals
+    }
+}
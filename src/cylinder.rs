@@ -0,0 +1,146 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+use crate::plane::planar_basis;
+
+/// Returns both roots of a·t² + b·t + c = 0 in ascending order, if real
+pub fn solve_quadratic(a: f32, b: f32, c: f32) -> Option<(f32, f32)> {
+    if a.abs() < 1e-8 {
+        // Degenerate case: linear equation
+        if b.abs() < 1e-8 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let sqrt_d = discriminant.sqrt();
+    let t0 = (-b - sqrt_d) / (2.0 * a);
+    let t1 = (-b + sqrt_d) / (2.0 * a);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Local frame of a round primitive: `axis` is the local Y, `tangent`/`bitangent` are X/Z
+#[derive(Clone, Copy)]
+pub struct AxisFrame {
+    pub origin: Vector3,
+    pub axis: Vector3,
+    pub tangent: Vector3,
+    pub bitangent: Vector3,
+}
+
+impl AxisFrame {
+    pub fn new(origin: Vector3, axis: Vector3) -> Self {
+        let axis = axis.normalized();
+        let (tangent, bitangent) = planar_basis(&axis);
+        AxisFrame { origin, axis, tangent, bitangent }
+    }
+
+    pub fn point_to_local(&self, p: &Vector3) -> Vector3 {
+        self.direction_to_local(&(*p - self.origin))
+    }
+
+    pub fn direction_to_local(&self, d: &Vector3) -> Vector3 {
+        Vector3::new(d.dot(self.tangent), d.dot(self.axis), d.dot(self.bitangent))
+    }
+
+    pub fn direction_to_world(&self, d: &Vector3) -> Vector3 {
+        self.tangent * d.x + self.axis * d.y + self.bitangent * d.z
+    }
+}
+
+/// Cylindrical mapping: u wraps around the axis, v runs along it
+pub fn cylindrical_uv(local_point: &Vector3, v: f32) -> (f32, f32) {
+    let u = local_point.z.atan2(local_point.x) / (2.0 * PI) + 0.5;
+    (u, 1.0 - v.clamp(0.0, 1.0))
+}
+
+/// Tangent of increasing u around the axis (in local space)
+pub fn cylindrical_tangent(local_point: &Vector3) -> Vector3 {
+    let tangent = Vector3::new(-local_point.z, 0.0, local_point.x);
+    if tangent.dot(tangent) < 1e-12 {
+        Vector3::new(1.0, 0.0, 0.0)
+    } else {
+        tangent.normalized()
+    }
+}
+
+/// Capped cylinder standing on `base` and extending `height` along `axis`
+#[derive(Clone)]
+pub struct Cylinder {
+    pub radius: f32,
+    pub height: f32,
+    pub material: Material,
+    frame: AxisFrame,
+}
+
+impl Cylinder {
+    pub fn new(base: Vector3, axis: Vector3, radius: f32, height: f32, material: Material) -> Self {
+        Cylinder {
+            radius,
+            height,
+            material,
+            frame: AxisFrame::new(base, axis),
+        }
+    }
+
+    /// Vertical cylinder (axis +Y), the usual case for trunks and towers
+    pub fn vertical(base: Vector3, radius: f32, height: f32, material: Material) -> Self {
+        Cylinder::new(base, Vector3::new(0.0, 1.0, 0.0), radius, height, material)
+    }
+}
+
+impl RayIntersect for Cylinder {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let o = self.frame.point_to_local(ray_origin);
+        let d = self.frame.direction_to_local(ray_direction);
+        let r2 = self.radius * self.radius;
+
+        // Candidatos: (t, normal local, (u, v), tangente local)
+        let mut best: Option<(f32, Vector3, (f32, f32), Vector3)> = None;
+        let mut consider = |t: f32, normal: Vector3, uv: (f32, f32), tangent: Vector3| {
+            if t > 0.0 && best.is_none_or(|(best_t, ..)| t < best_t) {
+                best = Some((t, normal, uv, tangent));
+            }
+        };
+
+        // Superficie lateral: x² + z² = r², con 0 <= y <= h
+        if let Some((t0, t1)) = solve_quadratic(d.x * d.x + d.z * d.z, 2.0 * (o.x * d.x + o.z * d.z), o.x * o.x + o.z * o.z - r2) {
+            for t in [t0, t1] {
+                let p = o + d * t;
+                if p.y >= 0.0 && p.y <= self.height {
+                    let normal = Vector3::new(p.x, 0.0, p.z) / self.radius;
+                    consider(t, normal, cylindrical_uv(&p, p.y / self.height), cylindrical_tangent(&p));
+                }
+            }
+        }
+
+        // Tapas inferior (y = 0) y superior (y = h)
+        if d.y.abs() > 1e-6 {
+            for (cap_y, normal_y) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (cap_y - o.y) / d.y;
+                let p = o + d * t;
+                if p.x * p.x + p.z * p.z <= r2 {
+                    let uv = (p.x / (2.0 * self.radius) + 0.5, p.z / (2.0 * self.radius) + 0.5);
+                    consider(t, Vector3::new(0.0, normal_y, 0.0), uv, Vector3::new(1.0, 0.0, 0.0));
+                }
+            }
+        }
+
+        match best {
+            Some((t, normal, (u, v), tangent)) => {
+                let point = *ray_origin + *ray_direction * t;
+                let normal = self.frame.direction_to_world(&normal);
+                let tangent = self.frame.direction_to_world(&tangent);
+                Intersect::new(point, normal, t, self.material.clone(), u, v).with_tangent(tangent)
+            }
+            None => Intersect::empty(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+use crate::plane::planar_basis;
+
+/// Flat circular disk defined by its center, normal and radius
+#[derive(Clone)]
+pub struct Disk {
+    pub center: Vector3,
+    pub normal: Vector3,
+    pub radius: f32,
+    pub material: Material,
+    pub tiling: (f32, f32), // Repeticiones de textura a lo largo del diámetro
+}
+
+impl Disk {
+    pub fn new(center: Vector3, normal: Vector3, radius: f32, material: Material) -> Self {
+        Disk {
+            center,
+            normal: normal.normalized(),
+            radius,
+            material,
+            tiling: (1.0, 1.0),
+        }
+    }
+
+    pub fn with_tiling(mut self, u_repeat: f32, v_repeat: f32) -> Self {
+        self.tiling = (u_repeat, v_repeat);
+        self
+    }
+
+    fn get_uv(&self, point: &Vector3) -> (f32, f32) {
+        let (tangent, bitangent) = planar_basis(&self.normal);
+        let local_point = *point - self.center;
+        let diameter = self.radius * 2.0;
+
+        // Proyección planar sobre el cuadrado que contiene al disco
+        let u = ((local_point.dot(tangent) / diameter + 0.5) * self.tiling.0).rem_euclid(1.0);
+        let v = ((local_point.dot(bitangent) / diameter + 0.5) * self.tiling.1).rem_euclid(1.0);
+        (u, 1.0 - v)
+    }
+}
+
+impl RayIntersect for Disk {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let denom = self.normal.dot(*ray_direction);
+
+        // Ray parallel to the disk
+        if denom.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+
+        let t = (self.center - *ray_origin).dot(self.normal) / denom;
+        if t <= 0.0 {
+            return Intersect::empty();
+        }
+
+        let point = *ray_origin + *ray_direction * t;
+        let offset = point - self.center;
+        if offset.dot(offset) > self.radius * self.radius {
+            return Intersect::empty();
+        }
+
+        let (u, v) = self.get_uv(&point);
+
+        Intersect::new(point, self.normal, t, self.material.clone(), u, v)
+    }
+}
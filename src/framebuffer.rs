@@ -2,6 +2,66 @@
 
 use raylib::prelude::*;
 
+// Tamaño de letra y márgenes de los paneles de texto superpuestos
+const OVERLAY_FONT_SIZE: i32 = 18;
+const OVERLAY_PADDING: i32 = 8;
+const OVERLAY_LINE_HEIGHT: i32 = OVERLAY_FONT_SIZE + 4;
+// Barras de los deslizadores, a la derecha del texto
+const SLIDER_GAP: i32 = 12;
+const SLIDER_WIDTH: i32 = 140;
+
+/// Block of text drawn over the rendered image, anchored at its top-left corner.
+/// Rows may carry a slider bar, filled to a fraction in [0, 1].
+pub struct OverlayPanel {
+    pub x: i32,
+    pub y: i32,
+    pub lines: Vec<String>,
+    pub sliders: Vec<(usize, f32)>, // Fila y fracción llena de cada deslizador
+}
+
+impl OverlayPanel {
+    pub fn new(x: i32, y: i32, lines: Vec<String>) -> Self {
+        OverlayPanel { x, y, lines, sliders: Vec::new() }
+    }
+
+    pub fn with_slider(mut self, row: usize, fraction: f32) -> Self {
+        self.sliders.push((row, fraction.clamp(0.0, 1.0)));
+        self
+    }
+
+    // Ancho de la línea más larga con la fuente de raylib
+    fn text_width(&self, window: &RaylibHandle) -> i32 {
+        self.lines.iter().map(|line| window.measure_text(line, OVERLAY_FONT_SIZE)).max().unwrap_or(0)
+    }
+
+    /// Height in pixels, padding included; it does not depend on the font metrics
+    pub fn height(&self) -> i32 {
+        self.lines.len() as i32 * OVERLAY_LINE_HEIGHT + 2 * OVERLAY_PADDING
+    }
+
+    /// Width and height in pixels, padding included
+    pub fn size(&self, window: &RaylibHandle) -> (i32, i32) {
+        let slider_space = if self.sliders.is_empty() { 0 } else { SLIDER_GAP + SLIDER_WIDTH };
+        (self.text_width(window) + slider_space + 2 * OVERLAY_PADDING, self.height())
+    }
+
+    /// Row under the window point (x, y), if it falls on the panel
+    pub fn row_at(&self, window: &RaylibHandle, x: f32, y: f32) -> Option<usize> {
+        let (width, height) = self.size(window);
+        let (x, y) = (x as i32 - self.x, y as i32 - self.y);
+        if x < 0 || x >= width || y < OVERLAY_PADDING || y >= height - OVERLAY_PADDING {
+            return None;
+        }
+        Some(((y - OVERLAY_PADDING) / OVERLAY_LINE_HEIGHT) as usize)
+    }
+
+    /// Slider fraction at window column `x`, clamped to the bar
+    pub fn slider_fraction(&self, window: &RaylibHandle, x: f32) -> f32 {
+        let start = (self.x + OVERLAY_PADDING + self.text_width(window) + SLIDER_GAP) as f32;
+        ((x - start) / SLIDER_WIDTH as f32).clamp(0.0, 1.0)
+    }
+}
+
 pub struct Framebuffer {
     pub width: u32,
     pub height: u32,
@@ -12,6 +72,7 @@ pub struct Framebuffer {
     cached_texture: Option<Texture2D>,
     buffer_dirty: bool,
     pixel_data: Vec<u32>, // Buffer de píxeles optimizado para blit
+    overlay: Vec<OverlayPanel>, // Texto sobre la imagen: no toca el buffer ni invalida la textura
 }
 
 impl Framebuffer {
@@ -27,6 +88,7 @@ impl Framebuffer {
             cached_texture: None,
             buffer_dirty: true,
             pixel_data: vec![0; pixel_count], // Buffer optimizado
+            overlay: Vec::new(),
         }
     }
 
@@ -60,6 +122,11 @@ impl Framebuffer {
         self.current_color = color;
     }
 
+    /// Replaces the text panels drawn over the image on the next swap
+    pub fn set_overlay(&mut self, panels: Vec<OverlayPanel>) {
+        self.overlay = panels;
+    }
+
     pub fn _render_to_file(&self, file_path: &str) {
         self.color_buffer.export_image(file_path);
     }
@@ -90,6 +157,24 @@ impl Framebuffer {
         if let Some(ref texture) = self.cached_texture {
             renderer.draw_texture(texture, 0, 0, Color::WHITE);
         }
+
+        // Paneles semitransparentes con una línea de texto por fila
+        for panel in &self.overlay {
+            let (width, height) = panel.size(&renderer);
+            renderer.draw_rectangle(panel.x, panel.y, width, height, Color::new(0, 0, 0, 170));
+            for (row, line) in panel.lines.iter().enumerate() {
+                let y = panel.y + OVERLAY_PADDING + row as i32 * OVERLAY_LINE_HEIGHT;
+                renderer.draw_text(line, panel.x + OVERLAY_PADDING, y, OVERLAY_FONT_SIZE, Color::WHITE);
+            }
+
+            // Deslizadores: fondo gris y la parte llena en claro
+            let slider_x = panel.x + OVERLAY_PADDING + panel.text_width(&renderer) + SLIDER_GAP;
+            for &(row, fraction) in &panel.sliders {
+                let y = panel.y + OVERLAY_PADDING + row as i32 * OVERLAY_LINE_HEIGHT + 4;
+                renderer.draw_rectangle(slider_x, y, SLIDER_WIDTH, OVERLAY_FONT_SIZE - 6, Color::new(80, 80, 80, 220));
+                renderer.draw_rectangle(slider_x, y, (SLIDER_WIDTH as f32 * fraction) as i32, OVERLAY_FONT_SIZE - 6, Color::new(230, 180, 90, 255));
+            }
+        }
     }
     
     // Método para copiar regiones de buffer (blit optimizado)
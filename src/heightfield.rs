@@ -0,0 +1,358 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+use crate::textures::TextureManager;
+
+// Un nivel de la pirámide de máximos (max-mipmap): rango de alturas de cada bloque de celdas
+#[derive(Clone)]
+struct MipLevel {
+    width: usize,
+    depth: usize,
+    min: Vec<f32>,
+    max: Vec<f32>,
+}
+
+/// Solid terrain block whose top surface follows a grid of height samples.
+/// The samples span `min.x..max.x` and `min.z..max.z`; a value of 0 maps to
+/// `min.y` and 1 to `max.y`. The sides and bottom are closed down to `min.y`.
+#[derive(Clone)]
+pub struct Heightfield {
+    pub material: Material,
+    pub tiling: (f32, f32), // Repeticiones de textura sobre toda la superficie
+    min: Vector3,
+    max: Vector3,
+    samples_x: usize,
+    samples_z: usize,
+    cell_size_x: f32,
+    cell_size_z: f32,
+    heights: Vec<f32>,     // Altura en el mundo de cada muestra
+    normals: Vec<Vector3>, // Normales suaves por vértice
+    levels: Vec<MipLevel>, // levels[0] = celdas individuales, el último nivel = todo el terreno
+}
+
+impl Heightfield {
+    pub fn new(samples_x: usize, samples_z: usize, values: &[f32], min: Vector3, max: Vector3, material: Material) -> Self {
+        assert!(samples_x >= 2 && samples_z >= 2, "Heightfield needs at least 2x2 samples");
+        assert_eq!(values.len(), samples_x * samples_z, "Heightfield sample count mismatch");
+
+        let heights: Vec<f32> = values.iter().map(|v| min.y + v.clamp(0.0, 1.0) * (max.y - min.y)).collect();
+        let cell_size_x = (max.x - min.x) / (samples_x - 1) as f32;
+        let cell_size_z = (max.z - min.z) / (samples_z - 1) as f32;
+
+        let mut heightfield = Heightfield {
+            material,
+            tiling: (1.0, 1.0),
+            min,
+            max,
+            samples_x,
+            samples_z,
+            cell_size_x,
+            cell_size_z,
+            heights,
+            normals: Vec::new(),
+            levels: Vec::new(),
+        };
+        heightfield.normals = heightfield.compute_normals();
+        heightfield.levels = heightfield.build_levels();
+        heightfield
+    }
+
+    /// Builds the terrain from a grayscale image already loaded in the texture manager
+    pub fn from_texture(texture_manager: &TextureManager, path: &str, min: Vector3, max: Vector3, material: Material) -> Result<Self, String> {
+        let (width, height, values) = texture_manager
+            .get_grayscale(path)
+            .ok_or_else(|| format!("Heightmap {} is not loaded", path))?;
+        if width < 2 || height < 2 {
+            return Err(format!("Heightmap {} is smaller than 2x2", path));
+        }
+        Ok(Heightfield::new(width, height, &values, min, max, material))
+    }
+
+    pub fn with_tiling(mut self, u_repeat: f32, v_repeat: f32) -> Self {
+        self.tiling = (u_repeat, v_repeat);
+        self
+    }
+
+    fn height(&self, i: usize, j: usize) -> f32 {
+        self.heights[j * self.samples_x + i]
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vector3 {
+        Vector3::new(
+            self.min.x + i as f32 * self.cell_size_x,
+            self.height(i, j),
+            self.min.z + j as f32 * self.cell_size_z,
+        )
+    }
+
+    // Normales por diferencias centrales (unilaterales en los bordes)
+    fn compute_normals(&self) -> Vec<Vector3> {
+        let mut normals = Vec::with_capacity(self.heights.len());
+        for j in 0..self.samples_z {
+            for i in 0..self.samples_x {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.samples_x - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.samples_z - 1));
+                let slope_x = (self.height(i1, j) - self.height(i0, j)) / ((i1 - i0) as f32 * self.cell_size_x);
+                let slope_z = (self.height(i, j1) - self.height(i, j0)) / ((j1 - j0) as f32 * self.cell_size_z);
+                normals.push(Vector3::new(-slope_x, 1.0, -slope_z).normalized());
+            }
+        }
+        normals
+    }
+
+    fn build_levels(&self) -> Vec<MipLevel> {
+        // Nivel base: rango de alturas de las cuatro esquinas de cada celda
+        let (width, depth) = (self.samples_x - 1, self.samples_z - 1);
+        let mut base = MipLevel { width, depth, min: Vec::with_capacity(width * depth), max: Vec::with_capacity(width * depth) };
+        for j in 0..depth {
+            for i in 0..width {
+                let corners = [self.height(i, j), self.height(i + 1, j), self.height(i, j + 1), self.height(i + 1, j + 1)];
+                base.min.push(corners.iter().copied().fold(f32::INFINITY, f32::min));
+                base.max.push(corners.iter().copied().fold(f32::NEG_INFINITY, f32::max));
+            }
+        }
+
+        // Cada nivel superior agrupa bloques de 2x2 del anterior
+        let mut levels = vec![base];
+        while let Some(previous) = levels.last().filter(|level| level.width > 1 || level.depth > 1) {
+            let (width, depth) = (previous.width.div_ceil(2), previous.depth.div_ceil(2));
+            let mut level = MipLevel { width, depth, min: vec![f32::INFINITY; width * depth], max: vec![f32::NEG_INFINITY; width * depth] };
+            for j in 0..previous.depth {
+                for i in 0..previous.width {
+                    let parent = (j / 2) * width + i / 2;
+                    let child = j * previous.width + i;
+                    level.min[parent] = level.min[parent].min(previous.min[child]);
+                    level.max[parent] = level.max[parent].max(previous.max[child]);
+                }
+            }
+            levels.push(level);
+        }
+        levels
+    }
+
+    // Altura de la superficie triangulada en un punto (x, z) dentro de la huella
+    fn surface_height(&self, x: f32, z: f32) -> f32 {
+        let fx = ((x - self.min.x) / self.cell_size_x).clamp(0.0, (self.samples_x - 1) as f32);
+        let fz = ((z - self.min.z) / self.cell_size_z).clamp(0.0, (self.samples_z - 1) as f32);
+        let i = (fx as usize).min(self.samples_x - 2);
+        let j = (fz as usize).min(self.samples_z - 2);
+        let (tx, tz) = (fx - i as f32, fz - j as f32);
+
+        let (h00, h10, h01, h11) = (self.height(i, j), self.height(i + 1, j), self.height(i, j + 1), self.height(i + 1, j + 1));
+        // Misma división en triángulos que intersect_cell
+        if tx >= tz {
+            h00 + tx * (h10 - h00) + tz * (h11 - h10)
+        } else {
+            h00 + tz * (h01 - h00) + tx * (h11 - h01)
+        }
+    }
+
+    // Caja de un nodo de la pirámide: (mínimo, máximo) en el mundo
+    fn node_bounds(&self, level: usize, i: usize, j: usize) -> (Vector3, Vector3) {
+        let cells = 1usize << level;
+        let (x0, z0) = (i * cells, j * cells);
+        let x1 = ((i + 1) * cells).min(self.samples_x - 1);
+        let z1 = ((j + 1) * cells).min(self.samples_z - 1);
+        let mip = &self.levels[level];
+        let index = j * mip.width + i;
+        (
+            Vector3::new(self.min.x + x0 as f32 * self.cell_size_x, mip.min[index], self.min.z + z0 as f32 * self.cell_size_z),
+            Vector3::new(self.min.x + x1 as f32 * self.cell_size_x, mip.max[index], self.min.z + z1 as f32 * self.cell_size_z),
+        )
+    }
+
+    // Recorrido de la pirámide de máximos de adelante hacia atrás.
+    // Devuelve (t, normal suave, tangente) del primer impacto con la superficie.
+    fn traverse(&self, level: usize, i: usize, j: usize, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, Vector3, Vector3)> {
+        if level == 0 {
+            return self.intersect_cell(i, j, ray_origin, ray_direction);
+        }
+
+        // Hijos del nodo que el rayo atraviesa, ordenados por distancia de entrada.
+        // Sus columnas no se solapan en XZ, así que el primer impacto encontrado es el más cercano.
+        // Como mucho cuatro hijos: array fijo en la pila, sin reservar memoria por nodo
+        let child_level = &self.levels[level - 1];
+        let mut children = [(0.0f32, 0usize, 0usize); 4];
+        let mut count = 0;
+        for (ci, cj) in [(2 * i, 2 * j), (2 * i + 1, 2 * j), (2 * i, 2 * j + 1), (2 * i + 1, 2 * j + 1)] {
+            if ci < child_level.width && cj < child_level.depth {
+                let (box_min, box_max) = self.node_bounds(level - 1, ci, cj);
+                if let Some((t_enter, _)) = ray_box(ray_origin, ray_direction, &box_min, &box_max) {
+                    children[count] = (t_enter, ci, cj);
+                    count += 1;
+                }
+            }
+        }
+        let children = &mut children[..count];
+        children.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        children
+            .iter()
+            .find_map(|&(_, ci, cj)| self.traverse(level - 1, ci, cj, ray_origin, ray_direction))
+    }
+
+    // Dos triángulos por celda: (00, 10, 11) y (00, 11, 01)
+    fn intersect_cell(&self, i: usize, j: usize, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, Vector3, Vector3)> {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let mut best: Option<(f32, Vector3, Vector3)> = None;
+
+        for triangle in [[0, 1, 2], [0, 2, 3]] {
+            let [a, b, c] = triangle.map(|k| corners[k]);
+            let (p0, p1, p2) = (self.vertex(a.0, a.1), self.vertex(b.0, b.1), self.vertex(c.0, c.1));
+            let Some((t, b1, b2)) = ray_triangle(ray_origin, ray_direction, &p0, &p1, &p2) else {
+                continue;
+            };
+            if best.is_none_or(|(best_t, ..)| t < best_t) {
+                let normal = (self.normals[a.1 * self.samples_x + a.0] * (1.0 - b1 - b2)
+                    + self.normals[b.1 * self.samples_x + b.0] * b1
+                    + self.normals[c.1 * self.samples_x + c.0] * b2)
+                    .normalized();
+                // Tangente: dirección +X sobre el triángulo, ortogonalizada con la normal suave
+                let along_x = if triangle == [0, 1, 2] { p1 - p0 } else { p1 - p2 };
+                let tangent = (along_x - normal * along_x.dot(normal)).normalized();
+                best = Some((t, normal, tangent));
+            }
+        }
+        best
+    }
+
+    // Caras laterales e inferior que cierran el sólido: (t, normal, tangente)
+    fn intersect_walls(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, Vector3, Vector3)> {
+        let mut best: Option<(f32, Vector3, Vector3)> = None;
+        let mut consider = |t: f32, normal: Vector3, tangent: Vector3| {
+            if t > 0.0 && best.is_none_or(|(best_t, ..)| t < best_t) {
+                best = Some((t, normal, tangent));
+            }
+        };
+
+        let x_tangent = Vector3::new(0.0, 0.0, 1.0);
+        let z_tangent = Vector3::new(1.0, 0.0, 0.0);
+
+        // Laterales en X
+        if ray_direction.x.abs() > 1e-6 {
+            for (x, sign) in [(self.min.x, -1.0), (self.max.x, 1.0)] {
+                let t = (x - ray_origin.x) / ray_direction.x;
+                let p = *ray_origin + *ray_direction * t;
+                if p.z >= self.min.z && p.z <= self.max.z && p.y >= self.min.y && p.y <= self.surface_height(x, p.z) {
+                    consider(t, Vector3::new(sign, 0.0, 0.0), x_tangent);
+                }
+            }
+        }
+
+        // Laterales en Z
+        if ray_direction.z.abs() > 1e-6 {
+            for (z, sign) in [(self.min.z, -1.0), (self.max.z, 1.0)] {
+                let t = (z - ray_origin.z) / ray_direction.z;
+                let p = *ray_origin + *ray_direction * t;
+                if p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.surface_height(p.x, z) {
+                    consider(t, Vector3::new(0.0, 0.0, sign), z_tangent);
+                }
+            }
+        }
+
+        // Base
+        if ray_direction.y.abs() > 1e-6 {
+            let t = (self.min.y - ray_origin.y) / ray_direction.y;
+            let p = *ray_origin + *ray_direction * t;
+            if p.x >= self.min.x && p.x <= self.max.x && p.z >= self.min.z && p.z <= self.max.z {
+                consider(t, Vector3::new(0.0, -1.0, 0.0), z_tangent);
+            }
+        }
+
+        best
+    }
+
+    // Proyección planar según la cara, con la misma escala que la superficie superior
+    fn get_uv(&self, point: &Vector3, normal: &Vector3, is_wall: bool) -> (f32, f32) {
+        let scale_x = self.tiling.0 / (self.max.x - self.min.x);
+        let scale_z = self.tiling.1 / (self.max.z - self.min.z);
+        let local_point = *point - self.min;
+
+        let (u, v) = if !is_wall || normal.y.abs() > 0.9 {
+            (local_point.x * scale_x, local_point.z * scale_z)
+        } else if normal.x.abs() > 0.9 {
+            (local_point.z * scale_z, local_point.y * scale_z)
+        } else {
+            (local_point.x * scale_x, local_point.y * scale_x)
+        };
+        (u.rem_euclid(1.0), 1.0 - v.rem_euclid(1.0))
+    }
+}
+
+impl RayIntersect for Heightfield {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let top = self.levels.len() - 1;
+        let surface = self.traverse(top, 0, 0, ray_origin, ray_direction).map(|hit| (hit, false));
+        let walls = self.intersect_walls(ray_origin, ray_direction).map(|hit| (hit, true));
+
+        let nearest = match (surface, walls) {
+            (Some(s), Some(w)) => Some(if s.0.0 <= w.0.0 { s } else { w }),
+            (s, w) => s.or(w),
+        };
+
+        match nearest {
+            Some(((t, normal, tangent), is_wall)) => {
+                let point = *ray_origin + *ray_direction * t;
+                let (u, v) = self.get_uv(&point, &normal, is_wall);
+                Intersect::new(point, normal, t, self.material.clone(), u, v).with_tangent(tangent)
+            }
+            None => Intersect::empty(),
+        }
+    }
+}
+
+// Prueba de slabs contra una caja alineada: (t de entrada, t de salida)
+fn ray_box(ray_origin: &Vector3, ray_direction: &Vector3, box_min: &Vector3, box_max: &Vector3) -> Option<(f32, f32)> {
+    let mut t_enter = f32::NEG_INFINITY;
+    let mut t_exit = f32::INFINITY;
+
+    for (origin, direction, low, high) in [
+        (ray_origin.x, ray_direction.x, box_min.x, box_max.x),
+        (ray_origin.y, ray_direction.y, box_min.y, box_max.y),
+        (ray_origin.z, ray_direction.z, box_min.z, box_max.z),
+    ] {
+        if direction.abs() < 1e-8 {
+            if origin < low || origin > high {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (low - origin) / direction;
+        let t2 = (high - origin) / direction;
+        t_enter = t_enter.max(t1.min(t2));
+        t_exit = t_exit.min(t1.max(t2));
+    }
+
+    if t_enter > t_exit || t_exit < 0.0 {
+        None
+    } else {
+        Some((t_enter, t_exit))
+    }
+}
+
+// Möller–Trumbore de doble cara: (t, b1, b2) con coordenadas baricéntricas de p1 y p2
+fn ray_triangle(ray_origin: &Vector3, ray_direction: &Vector3, p0: &Vector3, p1: &Vector3, p2: &Vector3) -> Option<(f32, f32, f32)> {
+    let edge1 = *p1 - *p0;
+    let edge2 = *p2 - *p0;
+    let h = ray_direction.cross(edge2);
+    let det = edge1.dot(h);
+    if det.abs() < 1e-9 {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = *ray_origin - *p0;
+    let b1 = s.dot(h) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let b2 = ray_direction.dot(q) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    if t > 0.0 { Some((t, b1, b2)) } else { None }
+}
@@ -0,0 +1,105 @@
+use std::cell::Cell;
+use crate::framebuffer::OverlayPanel;
+
+// Segundos de cada ventana de promedio: cifras estables pero con respuesta rápida
+const AVERAGE_WINDOW: f64 = 0.5;
+
+#[derive(Clone, Copy)]
+pub enum RayKind {
+    Primary,   // Desde la cámara
+    Shadow,    // Hacia la luz, desde superficies o desde la niebla
+    Secondary, // Reflexión y refracción
+}
+
+/// Rays traced while rendering, by kind. Rendering runs on one thread, so
+/// plain cells are enough to count through the shared `&Scene`.
+#[derive(Default)]
+pub struct RayCounters {
+    counts: [Cell<u64>; 3],
+}
+
+impl RayCounters {
+    pub fn record(&self, kind: RayKind) {
+        let count = &self.counts[kind as usize];
+        count.set(count.get() + 1);
+    }
+
+    // Cuentas desde la última llamada, en el orden de `RayKind`
+    fn take(&self) -> [u64; 3] {
+        self.counts.each_ref().map(|count| count.take())
+    }
+}
+
+/// Where the interactive renderer is in its refinement after a change
+pub enum RenderStage {
+    Adaptive,
+    Full,
+    Progressive { sample: u32, total: u32 },
+}
+
+/// Frame time and ray throughput, averaged over short windows
+#[derive(Default)]
+pub struct Hud {
+    pub visible: bool,
+    window_start: f64,
+    frames: u32,
+    rays: [u64; 3],
+    frame_ms: f64,
+    rays_per_second: [f64; 3],
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a finished frame, taking the rays it traced from `counters`;
+    /// `now` is the window clock in seconds
+    pub fn record_frame(&mut self, counters: &RayCounters, now: f64) {
+        for (total, rays) in self.rays.iter_mut().zip(counters.take()) {
+            *total += rays;
+        }
+        self.frames += 1;
+
+        let elapsed = now - self.window_start;
+        if elapsed >= AVERAGE_WINDOW {
+            self.frame_ms = elapsed * 1000.0 / self.frames as f64;
+            self.rays_per_second = self.rays.map(|rays| rays as f64 / elapsed);
+            self.window_start = now;
+            self.frames = 0;
+            self.rays = [0; 3];
+        }
+    }
+
+    /// Top-left panel with the averages and the renderer's level of detail
+    /// and refinement stage
+    pub fn panel(&self, lod: u32, stage: &RenderStage) -> OverlayPanel {
+        let fps = if self.frame_ms > 0.0 { 1000.0 / self.frame_ms } else { 0.0 };
+        let [primary, shadow, secondary] = self.rays_per_second.map(rate);
+        let mut lines = vec![
+            format!("FPS {:.1}  ({:.1} ms/frame)", fps, self.frame_ms),
+            format!("Rayos/s primarios  {}", primary),
+            format!("Rayos/s de sombra  {}", shadow),
+            format!("Rayos/s secundarios  {}", secondary),
+            format!("LOD {}", lod),
+        ];
+        lines.extend(match *stage {
+            RenderStage::Adaptive => vec!["Fase adaptativa".to_string()],
+            RenderStage::Full => vec!["Fase completa".to_string()],
+            RenderStage::Progressive { sample, total } => vec![
+                format!("Fase progresiva: {:.0}%", 100.0 * sample as f64 / total as f64),
+                format!("Muestras {} / {}", sample, total),
+            ],
+        });
+        OverlayPanel::new(10, 10, lines)
+    }
+}
+
+// Rayos por segundo en millones o miles
+fn rate(rays_per_second: f64) -> String {
+    if rays_per_second >= 1e6 {
+        format!("{:.2} M", rays_per_second / 1e6)
+    } else {
+        format!("{:.1} k", rays_per_second / 1e3)
+    }
+}
@@ -1,53 +1,82 @@
 use raylib::prelude::*;
+use std::cell::Cell;
 use std::f32::consts::PI;
 
 mod framebuffer;
 mod ray_intersect;
 mod cube;
+mod plane;
+mod quad;
+mod disk;
+mod cylinder;
+mod cone;
+mod capsule;
+mod csg;
+mod sdf;
+mod heightfield;
+mod voxel_grid;
 mod camera;
 mod light;
 mod material;
 mod textures;
 mod skybox;
-
-use framebuffer::Framebuffer;
+mod atmosphere;
+mod noise;
+mod procedural_texture;
+mod shader_graph;
+mod volume;
+mod transform;
+mod animation;
+mod sequence;
+mod bookmarks;
+mod spline;
+mod scene_edit;
+mod material_editor;
+mod hud;
+
+use framebuffer::{Framebuffer, OverlayPanel};
 use ray_intersect::{Intersect, RayIntersect};
 use cube::Cube;
-use camera::Camera;
+use plane::Plane;
+use quad::Quad;
+use disk::Disk;
+use cylinder::Cylinder;
+use cone::Cone;
+use capsule::Capsule;
+use csg::Csg;
+use sdf::{SdfObject, SdfShape};
+use heightfield::Heightfield;
+use voxel_grid::VoxelGrid;
+use camera::{ApertureShape, Camera, Projection};
 use light::Light;
 use material::{Material, vector3_to_color};
 use textures::TextureManager;
 use skybox::Skybox;
+use atmosphere::{Atmosphere, Fog, FogVolume};
+use volume::{DensityGrid, DensitySource, HeterogeneousVolume};
+use procedural_texture::{NoisePattern, ProceduralTexture};
+use transform::{MovingObject, Transform, TransformedObject};
+use animation::{Animation, CameraAnimation, Interpolation, LightAnimation, ObjectAnimation, Track};
+use sequence::SequenceSettings;
+use bookmarks::{Bookmark, Flythrough};
+use spline::{CameraPath, SplineKind};
+use scene_edit::{MaterialOverride, PrimitiveKind, Removed, Retuned, SceneEditor};
+use material_editor::PanelRow;
+use hud::{Hud, RayCounters, RayKind, RenderStage};
+use shader_graph::{GraphConnection, MaterialInput, ShaderNode, ShadingContext};
 
 const ORIGIN_BIAS: f32 = 1e-4;
-
-// ========== SISTEMA DE ROTACIÓN GLOBAL DE ESCENA ==========
-#[derive(Clone, Copy)]
-struct Matrix3 {
-    data: [[f32; 3]; 3],
-}
-
-impl Matrix3 {
-    fn rotation_y(angle: f32) -> Self {
-        let cos_a = angle.cos();
-        let sin_a = angle.sin();
-        Matrix3 {
-            data: [
-                [cos_a, 0.0, sin_a],
-                [0.0, 1.0, 0.0],
-                [-sin_a, 0.0, cos_a],
-            ]
-        }
-    }
-
-    fn transform_vector(&self, v: Vector3) -> Vector3 {
-        Vector3::new(
-            self.data[0][0] * v.x + self.data[0][1] * v.y + self.data[0][2] * v.z,
-            self.data[1][0] * v.x + self.data[1][1] * v.y + self.data[1][2] * v.z,
-            self.data[2][0] * v.x + self.data[2][1] * v.y + self.data[2][2] * v.z,
-        )
-    }
-}
+// Tinte del objeto seleccionado (naranja) y cuánto se mezcla con su color
+const SELECTION_TINT: Vector3 = Vector3::new(1.0, 0.55, 0.1);
+const SELECTION_TINT_STRENGTH: f32 = 0.35;
+// Segundos que se muestra un aviso abajo a la izquierda
+const MESSAGE_DURATION: f64 = 3.0;
+// Fases del refinado tras un cambio: render adaptativo hasta ADAPTIVE_FRAMES
+// frames, render completo hasta FULL_RENDER_FRAMES y después progresivo
+const ADAPTIVE_FRAMES: u32 = 8;
+const FULL_RENDER_FRAMES: u32 = 20;
+// Pasadas del render progresivo que se promedian si hay desenfoque de movimiento o de lente
+const ACCUMULATION_PASSES: u32 = 16;
 
 fn offset_origin(intersect: &Intersect, direction: &Vector3) -> Vector3 {
     let offset = intersect.normal * ORIGIN_BIAS;
@@ -85,67 +114,306 @@ fn refract(incident: &Vector3, normal: &Vector3, refractive_index: f32) -> Optio
     }
 }
 
-fn cast_shadow(
-    intersect: &Intersect,
-    light: &Light,
-    objects: &[Cube],
-) -> f32 {
-    let light_dir = (light.position - intersect.point).normalized();
-    let light_distance = (light.position - intersect.point).length();
+// Todo lo que un rayo puede encontrar: geometría, luz y entorno
+pub struct Scene<'a> {
+    pub objects: &'a [Box<dyn RayIntersect + 'a>],
+    pub light: &'a Light,
+    pub texture_manager: &'a TextureManager,
+    pub skybox: &'a Skybox,
+    pub atmosphere: &'a Atmosphere,
+    pub time: f32, // Segundos de animación al abrir el obturador: mueve texturas y materiales
+    pub shutter: f32, // Segundos de escena con el obturador abierto (0 = sin desenfoque)
+    pub camera_open: Option<&'a Camera>, // Cámara al abrir el obturador, si se ha movido desde entonces
+    pub selected: Option<usize>, // Objeto seleccionado: se tiñe donde la cámara lo ve directamente
+    pub rays: &'a RayCounters,   // Rayos trazados por tipo, para las estadísticas del HUD
+}
 
-    let shadow_ray_origin = offset_origin(intersect, &light_dir);
+// Número pseudoaleatorio en [0, 1) fijo para cada píxel, muestra y dimensión
+// (tiempo, lente...): desordena entre píxeles pero las imágenes son repetibles
+fn pixel_hash(x: u32, y: u32, sample: u32, dimension: u32) -> f32 {
+    let mut hash = x.wrapping_mul(0x8da6_b343) ^ y.wrapping_mul(0xd816_3841) ^ sample.wrapping_mul(0xcb1a_b31f);
+    hash ^= dimension.wrapping_mul(0x9e37_79b9);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^= hash >> 15;
+    (hash & 0xffff) as f32 / 65536.0
+}
+
+// Instante del obturador de cada muestra de un píxel, en [0, 1): estratificado entre muestras
+fn shutter_sample(x: u32, y: u32, sample: u32, samples: u32) -> f32 {
+    (sample as f32 + pixel_hash(x, y, sample, 0)) / samples as f32
+}
+
+// Punto de la lente de cada muestra de un píxel, en [0, 1)²
+fn lens_sample(x: u32, y: u32, sample: u32) -> (f32, f32) {
+    (pixel_hash(x, y, sample, 1), pixel_hash(x, y, sample, 2))
+}
+
+// Radiancia de una muestra de un píxel. La muestra fija su instante dentro del
+// obturador y su punto en la lente; si la cámara se movió, se interpola desde
+// su posición al abrir el obturador hasta `camera` (al cerrarlo)
+fn trace_pixel(camera: &Camera, scene: &Scene, (x, y): (u32, u32), (width, height): (u32, u32), sample: u32, samples: u32) -> Vector3 {
+    let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
+    let screen_y = -(2.0 * y as f32) / height as f32 + 1.0;
+    let aspect_ratio = width as f32 / height as f32;
+
+    let time = shutter_sample(x, y, sample, samples);
+    let lens = lens_sample(x, y, sample);
+    let ray = match scene.camera_open {
+        Some(camera_open) => camera_open.lerp(camera, time).primary_ray(screen_x, screen_y, aspect_ratio, lens),
+        None => camera.primary_ray(screen_x, screen_y, aspect_ratio, lens),
+    };
+
+    match ray {
+        Some((origin, direction)) => cast_ray(&origin, &direction, scene, time, 0),
+        None => Vector3::zero(), // Fuera de la imagen de la proyección (borde del ojo de pez)
+    }
+}
+
+/// What the camera sees under a window pixel: the object's index in the
+/// scene list and the hit on it (material, point, normal, UV)
+struct Pick {
+    index: usize,
+    hit: Intersect,
+}
+
+// Consulta de selección: primer objeto bajo el píxel (x, y) de la ventana
+fn pick(camera: &Camera, objects: &[Box<dyn RayIntersect + '_>], x: f32, y: f32, width: f32, height: f32) -> Option<Pick> {
+    // La muestra (0, 0) es el centro de la lente: el rayo sin desenfoque
+    let (origin, direction) = camera.primary_ray((2.0 * x) / width - 1.0, -(2.0 * y) / height + 1.0, width / height, (0.0, 0.0))?;
+
+    nearest_hit(&origin, &direction, objects).map(|(index, hit)| Pick { index, hit })
+}
+
+// Primer impacto del rayo contra la escena y su índice (consultas fuera del render)
+fn nearest_hit(origin: &Vector3, direction: &Vector3, objects: &[Box<dyn RayIntersect + '_>]) -> Option<(usize, Intersect)> {
+    objects
+        .iter()
+        .map(|object| object.ray_intersect(origin, direction))
+        .enumerate()
+        .filter(|(_, intersect)| intersect.is_intersecting)
+        .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+}
+
+// Material con el que se ve el objeto seleccionado: el asignado en el editor o
+// el suyo con los ajustes de su material con nombre
+fn shown_material(selection: &Pick, editor: &SceneEditor) -> Material {
+    editor.material(selection.index).cloned().unwrap_or_else(|| {
+        let mut material = selection.hit.material.clone();
+        editor.retune(&mut material);
+        material
+    })
+}
+
+// Líneas del inspector del objeto seleccionado
+fn inspector_lines(selection: &Pick, editor: &SceneEditor) -> Vec<String> {
+    let (hit, material) = (&selection.hit, &shown_material(selection, editor));
+    let edit = &editor.slots()[selection.index];
+    let optional = |id: &Option<String>| id.clone().unwrap_or_else(|| "-".to_string());
+    vec![
+        format!("Objeto #{} (base #{})", selection.index, edit.source),
+        format!(
+            "Desplazado ({:.2}, {:.2}, {:.2})  Escala {:.2}",
+            edit.translation.x, edit.translation.y, edit.translation.z, edit.scale
+        ),
+        format!("Material editado: {}", edit.material.map_or("-", |material| editor.material_name(material))),
+        format!("Punto  ({:.2}, {:.2}, {:.2})", hit.point.x, hit.point.y, hit.point.z),
+        format!("Normal ({:.2}, {:.2}, {:.2})", hit.normal.x, hit.normal.y, hit.normal.z),
+        format!("UV     ({:.3}, {:.3})", hit.u, hit.v),
+        format!("Difuso ({:.2}, {:.2}, {:.2})", material.diffuse.x, material.diffuse.y, material.diffuse.z),
+        format!(
+            "Albedo [{:.2}, {:.2}, {:.2}, {:.2}]",
+            material.albedo[0], material.albedo[1], material.albedo[2], material.albedo[3]
+        ),
+        format!("Especular {:.1}  IOR {:.2}", material.specular, material.refractive_index),
+        format!("Textura {}  Normal map {}", optional(&material.texture_id), optional(&material.normal_map_id)),
+        format!("Grafo de material: {}", if material.graph.is_some() { "sí" } else { "no" }),
+    ]
+}
+
+// Paneles del objeto seleccionado a partir de la columna `x`: el inspector y,
+// debajo, el editor de materiales si está abierto (sin salirse de la ventana)
+fn selection_panels(
+    window: &RaylibHandle,
+    selection: &Pick,
+    editor: &SceneEditor,
+    material_editor_open: bool,
+    x: i32,
+    window_width: i32,
+) -> Vec<OverlayPanel> {
+    let inspector = OverlayPanel::new(x, 10, inspector_lines(selection, editor));
+    let below = inspector.y + inspector.height() + 10;
+    let mut panels = vec![inspector];
+    if material_editor_open {
+        let name = editor
+            .named_material(selection.index, &selection.hit.material)
+            .map_or("sin nombre", |material| editor.material_name(material));
+        let mut panel = material_editor::panel(x, below, name, &shown_material(selection, editor));
+        panel.x = panel.x.min(window_width - panel.size(window).0 - 10);
+        panels.push(panel);
+    }
+    panels
+}
+
+// Panel abajo a la izquierda con el último aviso, mientras dura
+fn message_panel(message: Option<&(String, f64)>, now: f64, window_height: i32) -> Option<OverlayPanel> {
+    let (message, _) = message.filter(|(_, shown)| now - shown < MESSAGE_DURATION)?;
+    let mut panel = OverlayPanel::new(10, 0, vec![message.clone()]);
+    panel.y = window_height - panel.height() - 10;
+    Some(panel)
+}
 
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
-            return 1.0;
+// Cámara con colisiones: el ojo no atraviesa superficies al moverse desde
+// `previous_eye` y se aparta de las que queden a menos de `min_distance`.
+// En vuelo libre el centro acompaña al ojo para que la vista no gire.
+fn avoid_collisions(camera: &mut Camera, previous_eye: Vector3, objects: &[Box<dyn RayIntersect + '_>], min_distance: f32, free_fly: bool) {
+    let mut eye = camera.eye;
+
+    // Barrido del movimiento: se detiene antes de la primera superficie del camino
+    let travel = eye - previous_eye;
+    let travel_length = travel.length();
+    if travel_length > 0.0 {
+        let direction = travel / travel_length;
+        if let Some((_, hit)) = nearest_hit(&previous_eye, &direction, objects).filter(|(_, hit)| hit.distance < travel_length + min_distance) {
+            eye = previous_eye + direction * (hit.distance - min_distance).max(0.0);
         }
     }
 
-    0.0
+    // Superficies cercanas en los ejes de la cámara: se empuja el ojo por su normal
+    for direction in [camera.right, -camera.right, camera.up, -camera.up, camera.forward, -camera.forward] {
+        if let Some((_, hit)) = nearest_hit(&eye, &direction, objects).filter(|(_, hit)| hit.distance < min_distance) {
+            let normal = if hit.normal.dot(direction) > 0.0 { -hit.normal } else { hit.normal };
+            eye += normal * (min_distance - hit.distance);
+        }
+    }
+
+    if eye != camera.eye {
+        if free_fly {
+            camera.center += eye - camera.eye;
+        }
+        camera.eye = eye;
+        camera.update_basis_vectors();
+    }
+}
+
+// ¿Hay algún objeto entre el punto y la luz en ese instante del obturador?
+fn is_occluded(origin: &Vector3, scene: &Scene, time: f32) -> bool {
+    find_occluder(origin, scene, time, None).is_some()
+}
+
+// Índice de un objeto entre `origin` y la luz; `hint` se prueba primero (el que
+// tapaba el punto anterior de una marcha suele tapar también el siguiente)
+fn find_occluder(origin: &Vector3, scene: &Scene, time: f32, hint: Option<usize>) -> Option<usize> {
+    let light = scene.light;
+    let light_dir = (light.position - *origin).normalized();
+    let light_distance = (light.position - *origin).length();
+    let blocks = |index: usize| {
+        let shadow_intersect = scene.objects[index].ray_intersect_at(origin, &light_dir, time);
+        shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance
+    };
+
+    scene.rays.record(RayKind::Shadow);
+    if let Some(hint) = hint.filter(|&hint| blocks(hint)) {
+        return Some(hint);
+    }
+    (0..scene.objects.len()).filter(|&index| Some(index) != hint).find(|&index| blocks(index))
+}
+
+fn cast_shadow(
+    intersect: &Intersect,
+    scene: &Scene,
+    time: f32,
+) -> f32 {
+    let light_dir = (scene.light.position - intersect.point).normalized();
+    let shadow_ray_origin = offset_origin(intersect, &light_dir);
+
+    if is_occluded(&shadow_ray_origin, scene, time) { 1.0 } else { 0.0 }
 }
 
 pub fn cast_ray(
     ray_origin: &Vector3,
     ray_direction: &Vector3,
-    objects: &[Cube],
-    light: &Light,
-    texture_manager: &TextureManager,
-    skybox: &Skybox,
+    scene: &Scene,
+    time: f32, // Instante del obturador en [0, 1]; los rayos secundarios lo heredan
     depth: u32,
 ) -> Vector3 {
     if depth > 3 {
-        return skybox.get_color(ray_direction);
+        return scene.skybox.get_color(ray_direction);
     }
+    scene.rays.record(if depth == 0 { RayKind::Primary } else { RayKind::Secondary });
 
     let mut intersect = Intersect::empty();
     let mut zbuffer = f32::INFINITY;
+    let mut hit_index = None;
 
-    for object in objects {
-        let i = object.ray_intersect(ray_origin, ray_direction);
+    for (index, object) in scene.objects.iter().enumerate() {
+        let i = object.ray_intersect_at(ray_origin, ray_direction, time);
         if i.is_intersecting && i.distance < zbuffer {
             zbuffer = i.distance;
             intersect = i;
+            hit_index = Some(index);
         }
     }
 
-    if !intersect.is_intersecting {
-        return skybox.get_color(ray_direction);
+    // El medio atenúa lo que se ve detrás y añade la luz que dispersa hacia la cámara
+    let (distance, color) = if intersect.is_intersecting {
+        let mut color = shade(ray_origin, ray_direction, &intersect, scene, time, depth);
+        if depth == 0 && hit_index.is_some() && hit_index == scene.selected {
+            color = color * (1.0 - SELECTION_TINT_STRENGTH) + SELECTION_TINT * SELECTION_TINT_STRENGTH;
+        }
+        (intersect.distance, color)
+    } else {
+        (f32::INFINITY, scene.skybox.get_color(ray_direction))
+    };
+
+    // Solo los rayos primarios trazan sombras dentro del medio; en reflejos y
+    // refracciones basta una marcha corta con el medio iluminado
+    if depth > 0 {
+        return scene.atmosphere.apply_secondary(ray_origin, ray_direction, distance, color, scene.light);
     }
+    let last_occluder = Cell::new(None);
+    scene.atmosphere.apply(ray_origin, ray_direction, distance, color, scene.light, |point| {
+        match find_occluder(point, scene, time, last_occluder.get()) {
+            Some(occluder) => {
+                last_occluder.set(Some(occluder));
+                0.0
+            }
+            None => 1.0,
+        }
+    })
+}
+
+// Color de la superficie impactada: Phong con normal map, reflexión y refracción
+fn shade(
+    ray_origin: &Vector3,
+    ray_direction: &Vector3,
+    intersect: &Intersect,
+    scene: &Scene,
+    time: f32,
+    depth: u32,
+) -> Vector3 {
+    let (light, texture_manager) = (scene.light, scene.texture_manager);
 
     let light_dir = (light.position - intersect.point).normalized();
     let view_dir = (*ray_origin - intersect.point).normalized();
 
+    // Las UV fluyen con el tiempo; las texturas se repiten al salir de [0, 1]
+    let (flow_u, flow_v) = intersect.material.uv_flow;
+    let scene_time = scene.time + time * scene.shutter;
+    let u = (intersect.u + flow_u * scene_time).rem_euclid(1.0);
+    let v = (intersect.v + flow_v * scene_time).rem_euclid(1.0);
+
     let mut normal = intersect.normal;
     if let Some(normal_map_path) = &intersect.material.normal_map_id {
         let texture = texture_manager.get_texture(normal_map_path).unwrap();
         let width = texture.width() as u32;
         let height = texture.height() as u32;
-        let tx = (intersect.u * width as f32) as u32;
-        let ty = (intersect.v * height as f32) as u32;
+        let tx = (u * width as f32) as u32;
+        let ty = (v * height as f32) as u32;
 
         if let Some(tex_normal) = texture_manager.get_normal_from_map(normal_map_path, tx, ty) {
-            let tangent = Vector3::new(normal.y, -normal.x, 0.0).normalized();
+            // Usar la tangente analítica de la primitiva si la proporciona
+            let tangent = intersect.tangent.unwrap_or_else(|| Vector3::new(normal.y, -normal.x, 0.0).normalized());
             let bitangent = normal.cross(tangent);
             
             let transformed_normal_x = tex_normal.x * tangent.x + tex_normal.y * bitangent.x + tex_normal.z * normal.x;
@@ -156,89 +424,97 @@ pub fn cast_ray(
         }
     }
 
+    let base_color = if let Some(texture_path) = &intersect.material.texture_id {
+        texture_manager.sample_color(texture_path, u, v)
+    } else {
+        intersect.material.diffuse
+    };
+
+    // Grafo de nodos: cada entrada conectada sustituye al valor constante del material
+    let graph = intersect.material.graph.as_deref();
+    let mut context = ShadingContext {
+        point: intersect.point,
+        object_point: intersect.object_point,
+        normal,
+        view_dir,
+        u,
+        v,
+        base_color,
+        time: scene_time,
+        texture_manager,
+    };
+    let input = |input: MaterialInput, context: &ShadingContext| {
+        graph.and_then(|graph| graph.input(input)).map(|node| node.evaluate(context))
+    };
+
+    // La normal va primero: el resto de nodos ya ve la superficie con relieve
+    if let Some(graph_normal) = input(MaterialInput::Normal, &context) {
+        normal = graph_normal.normalized();
+        context.normal = normal;
+    }
+
     let reflect_dir = reflect(&-light_dir, &normal).normalized();
 
-    let shadow_intensity = cast_shadow(&intersect, light, objects);
+    let shadow_intensity = cast_shadow(intersect, scene, time);
     let light_intensity = light.intensity * (1.0 - shadow_intensity);
 
-    let diffuse_color = if let Some(texture_path) = &intersect.material.texture_id {
-        let texture = texture_manager.get_texture(texture_path).unwrap();
-        let width = texture.width() as u32;
-        let height = texture.height() as u32;
-        let tx = (intersect.u * width as f32) as u32;
-        let ty = (intersect.v * height as f32) as u32;
-        let color = texture_manager.get_pixel_color(texture_path, tx, ty);
-        color
-    } else {
-        intersect.material.diffuse
-    };
+    let diffuse_color = input(MaterialInput::Color, &context).unwrap_or(base_color);
 
     let diffuse_intensity = normal.dot(light_dir).max(0.0) * light_intensity;
     let diffuse = diffuse_color * diffuse_intensity;
 
-    let specular_intensity = view_dir.dot(reflect_dir).max(0.0).powf(intersect.material.specular) * light_intensity;
+    // La rugosidad abre el brillo especular (exponente más bajo)
+    let specular_exponent = input(MaterialInput::Specular, &context).map_or(intersect.material.specular, |value| value.x.max(1.0));
+    let roughness = input(MaterialInput::Roughness, &context).map_or(0.0, |value| value.x.clamp(0.0, 1.0));
+    let shininess = specular_exponent * (1.0 - 0.9 * roughness);
+    let specular_intensity = view_dir.dot(reflect_dir).max(0.0).powf(shininess) * light_intensity;
     let light_color_v3 = Vector3::new(light.color.r as f32 / 255.0, light.color.g as f32 / 255.0, light.color.b as f32 / 255.0);
     let specular = light_color_v3 * specular_intensity;
 
     let albedo = intersect.material.albedo;
     let phong_color = diffuse * albedo[0] + specular * albedo[1];
 
-    let reflectivity = intersect.material.albedo[2];
+    let reflectivity = input(MaterialInput::Reflectivity, &context).map_or(albedo[2], |value| value.x.clamp(0.0, 1.0));
     let reflect_color = if reflectivity > 0.0 {
         let reflect_dir = reflect(ray_direction, &normal).normalized();
-        let reflect_origin = offset_origin(&intersect, &reflect_dir);
-        cast_ray(&reflect_origin, &reflect_dir, objects, light, texture_manager, skybox, depth + 1)
+        let reflect_origin = offset_origin(intersect, &reflect_dir);
+        cast_ray(&reflect_origin, &reflect_dir, scene, time, depth + 1)
     } else {
         Vector3::zero()
     };
 
-    let transparency = intersect.material.albedo[3];
+    let transparency = input(MaterialInput::Transparency, &context).map_or(albedo[3], |value| value.x.clamp(0.0, 1.0));
     let refract_color = if transparency > 0.0 {
         if let Some(refract_dir) = refract(ray_direction, &normal, intersect.material.refractive_index) {
-            let refract_origin = offset_origin(&intersect, &refract_dir);
-            cast_ray(&refract_origin, &refract_dir, objects, light, texture_manager, skybox, depth + 1)
+            let refract_origin = offset_origin(intersect, &refract_dir);
+            cast_ray(&refract_origin, &refract_dir, scene, time, depth + 1)
         } else {
             let reflect_dir = reflect(ray_direction, &normal).normalized();
-            let reflect_origin = offset_origin(&intersect, &reflect_dir);
-            cast_ray(&reflect_origin, &reflect_dir, objects, light, texture_manager, skybox, depth + 1)
+            let reflect_origin = offset_origin(intersect, &reflect_dir);
+            cast_ray(&reflect_origin, &reflect_dir, scene, time, depth + 1)
         }
     } else {
         Vector3::zero()
     };
 
-    phong_color * (1.0 - reflectivity - transparency) + reflect_color * reflectivity + refract_color * transparency
+    let emission = input(MaterialInput::Emission, &context).unwrap_or(Vector3::zero());
+
+    phong_color * (1.0 - reflectivity - transparency) + reflect_color * reflectivity + refract_color * transparency + emission
 }
 
 pub fn render(
     framebuffer: &mut Framebuffer,
-    objects: &[Cube],
+    scene: &Scene,
     camera: &Camera,
-    light: &Light,
-    texture_manager: &TextureManager,
-    skybox: &Skybox,
 ) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+    let size = (framebuffer.width, framebuffer.height);
 
     // Limpiar buffer con blit optimizado
     framebuffer.clear();
 
     for y in 0..framebuffer.height {
         for x in 0..framebuffer.width {
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-
-            let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
-            
-            let rotated_direction = camera.basis_change(&ray_direction);
-
-            let pixel_color_v3 = cast_ray(&camera.eye, &rotated_direction, objects, light, texture_manager, skybox, 0);
+            let pixel_color_v3 = trace_pixel(camera, scene, (x, y), size, 0, 1);
             let pixel_color = vector3_to_color(pixel_color_v3);
 
             framebuffer.set_current_color(pixel_color);
@@ -247,21 +523,29 @@ pub fn render(
     }
 }
 
+// Render a un buffer de radiancia lineal, sin pasar a 8 bits (secuencias offline);
+// cada píxel promedia `samples` rayos repartidos por el obturador y la lente
+fn render_radiance(width: u32, height: u32, scene: &Scene, camera: &Camera, samples: u32) -> Vec<Vector3> {
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let radiance = (0..samples).fold(Vector3::zero(), |sum, sample| {
+                sum + trace_pixel(camera, scene, (x, y), (width, height), sample, samples)
+            });
+            pixels.push(radiance / samples as f32);
+        }
+    }
+    pixels
+}
+
 // Renderizado adaptativo con LOD (Level of Detail) suave y temporal accumulation
 pub fn render_adaptive(
     framebuffer: &mut Framebuffer,
-    objects: &[Cube],
+    scene: &Scene,
     camera: &Camera,
-    light: &Light,
-    texture_manager: &TextureManager,
-    skybox: &Skybox,
     lod_level: u32, // 1 = alta calidad, 4 = baja calidad
 ) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+    let size = (framebuffer.width, framebuffer.height);
 
     // No hacer clear si LOD es alto (para acumulación temporal)
     if lod_level >= 4 {
@@ -292,16 +576,7 @@ pub fn render_adaptive(
                 (x, y)
             };
 
-            let screen_x = (2.0 * actual_x as f32) / width - 1.0;
-            let screen_y = -(2.0 * actual_y as f32) / height + 1.0;
-
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-
-            let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
-            let rotated_direction = camera.basis_change(&ray_direction);
-
-            let pixel_color_v3 = cast_ray(&camera.eye, &rotated_direction, objects, light, texture_manager, skybox, 0);
+            let pixel_color_v3 = trace_pixel(camera, scene, (actual_x, actual_y), size, 0, 1);
             let pixel_color = vector3_to_color(pixel_color_v3);
 
             // Aplicar el color con estrategias diferentes según LOD
@@ -372,34 +647,18 @@ fn fill_adaptive_block(
 // Renderizado rápido a baja resolución para movimiento de cámara
 pub fn render_fast(
     framebuffer: &mut Framebuffer,
-    objects: &[Cube],
+    scene: &Scene,
     camera: &Camera,
-    light: &Light,
-    texture_manager: &TextureManager,
-    skybox: &Skybox,
     scale_factor: u32, // Factor de escala (2, 4, etc.)
 ) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+    let size = (framebuffer.width, framebuffer.height);
 
     framebuffer.clear();
 
     // Renderizar solo cada N píxeles y luego hacer upscale
     for y in (0..framebuffer.height).step_by(scale_factor as usize) {
         for x in (0..framebuffer.width).step_by(scale_factor as usize) {
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-
-            let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
-            let rotated_direction = camera.basis_change(&ray_direction);
-
-            let pixel_color_v3 = cast_ray(&camera.eye, &rotated_direction, objects, light, texture_manager, skybox, 0);
+            let pixel_color_v3 = trace_pixel(camera, scene, (x, y), size, 0, 1);
             let pixel_color = vector3_to_color(pixel_color_v3);
 
             framebuffer.set_current_color(pixel_color);
@@ -421,78 +680,149 @@ pub fn render_fast(
 // Renderizado progresivo para mejor rendimiento interactivo
 pub fn render_progressive(
     framebuffer: &mut Framebuffer,
-    objects: &[Cube],
+    scene: &Scene,
     camera: &Camera,
-    light: &Light,
-    texture_manager: &TextureManager,
-    skybox: &Skybox,
     samples_per_frame: u32,
     current_sample: &mut u32,
+    accumulation: &mut [Vector3],
+    passes: u32,
 ) -> bool {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+    let size = (framebuffer.width, framebuffer.height);
 
     let total_pixels = framebuffer.width * framebuffer.height;
+    let total_samples = total_pixels * passes;
     
     // Solo limpiar si es el primer sample (evitar pantalla negra)
     if *current_sample == 0 {
         framebuffer.clear();
     }
 
-    let start_pixel = *current_sample;
-    let end_pixel = (start_pixel + samples_per_frame).min(total_pixels);
+    let start_sample = *current_sample;
+    let end_sample = (start_sample + samples_per_frame).min(total_samples);
 
-    // Renderizar solo la porción asignada
-    for pixel_index in start_pixel..end_pixel {
+    // Renderizar solo la porción asignada. Cada pasada recorre todos los píxeles con
+    // la siguiente muestra del obturador y la lente, y el píxel muestra la media
+    for sample_index in start_sample..end_sample {
+        let pass = sample_index / total_pixels;
+        let pixel_index = sample_index % total_pixels;
         let x = pixel_index % framebuffer.width;
         let y = pixel_index / framebuffer.width;
 
-        let screen_x = (2.0 * x as f32) / width - 1.0;
-        let screen_y = -(2.0 * y as f32) / height + 1.0;
-
-        let screen_x = screen_x * aspect_ratio * perspective_scale;
-        let screen_y = screen_y * perspective_scale;
-
-        let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
-        let rotated_direction = camera.basis_change(&ray_direction);
-
-        let pixel_color_v3 = cast_ray(&camera.eye, &rotated_direction, objects, light, texture_manager, skybox, 0);
-        let pixel_color = vector3_to_color(pixel_color_v3);
+        let radiance = trace_pixel(camera, scene, (x, y), size, pass, passes);
+        let accumulated = &mut accumulation[pixel_index as usize];
+        *accumulated = if pass == 0 { radiance } else { *accumulated + radiance };
+        let pixel_color = vector3_to_color(*accumulated / (pass + 1) as f32);
 
         framebuffer.set_current_color(pixel_color);
         framebuffer.set_pixel(x, y);
     }
 
-    *current_sample = end_pixel;
+    *current_sample = end_sample;
     
     // Retornar true si el renderizado está completo
-    *current_sample >= total_pixels
+    *current_sample >= total_samples
 }
 
 // ========== FUNCIONES DE TRANSFORMACIÓN GLOBAL ==========
-fn create_rotated_objects(base_objects: &[Cube], scene_rotation_angle: f32) -> Vec<Cube> {
-    // Optimización: calcular la matriz una sola vez
-    let rotation_matrix = Matrix3::rotation_y(scene_rotation_angle);
-    
+// Tiempo de escena y rotación global al abrir y al cerrar el obturador
+#[derive(Clone, Copy)]
+struct Shutter {
+    open_time: f32,
+    close_time: f32,
+    open_rotation: f32,
+    close_rotation: f32,
+}
+
+impl Shutter {
+    // Obturador instantáneo: sin desenfoque de movimiento
+    fn instant(time: f32, rotation: f32) -> Self {
+        Shutter {
+            open_time: time,
+            close_time: time,
+            open_rotation: rotation,
+            close_rotation: rotation,
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.open_time != self.close_time || self.open_rotation != self.close_rotation
+    }
+
+    fn time_at(&self, t: f32) -> f32 {
+        self.open_time + (self.close_time - self.open_time) * t
+    }
+
+    fn rotation_at(&self, t: f32) -> f32 {
+        self.open_rotation + (self.close_rotation - self.open_rotation) * t
+    }
+}
+
+// Vista de los objetos durante el obturador: cada objeto animado se mueve según
+// sus keyframes y después todo gira con la rotación global de la escena. Lo que
+// se mueve mientras el obturador está abierto se evalúa en el instante de cada rayo.
+fn create_frame_objects<'a>(
+    base_objects: &'a [Box<dyn RayIntersect>],
+    editor: &'a SceneEditor,
+    shutter: Shutter,
+    animation: &'a Animation,
+) -> Vec<Box<dyn RayIntersect + 'a>> {
+    let rotating = shutter.open_rotation != shutter.close_rotation;
+    let animating = shutter.open_time != shutter.close_time;
+    let tuned = editor.tuned_materials().next().is_some();
+
     // Pre-reservar el vector para evitar realocaciones
-    let mut rotated_objects = Vec::with_capacity(base_objects.len());
-    
-    // Aplicar la rotación usando iterador para mejor rendimiento
-    for object in base_objects {
-        let mut rotated_object = object.clone();
-        rotated_object.center = rotation_matrix.transform_vector(object.center);
-        rotated_objects.push(rotated_object);
+    let mut frame_objects: Vec<Box<dyn RayIntersect + 'a>> = Vec::with_capacity(editor.slots().len());
+
+    // Envolver cada objeto en vez de clonarlo: sirve para cualquier primitiva.
+    // Las copias del editor envuelven el mismo objeto base con otra edición.
+    for (index, slot) in editor.slots().iter().enumerate() {
+        if slot.deleted {
+            frame_objects.push(Box::new(Removed));
+            continue;
+        }
+        let object = base_objects[slot.source].as_ref();
+        let object_animation = animation.object(slot.source);
+        let edit = slot.transform();
+        let transform_at = move |t: f32| {
+            let scene_rotation = Transform::rotation_y(shutter.rotation_at(t));
+            match object_animation {
+                Some(object_animation) => object_animation.transform_at(shutter.time_at(t)).then(&edit).then(&scene_rotation),
+                None => edit.then(&scene_rotation),
+            }
+        };
+
+        let still = !slot.is_moved() && object_animation.is_none() && shutter.open_rotation == 0.0 && !rotating;
+        let frame_object: Box<dyn RayIntersect + 'a> = if still {
+            // Optimización: sin edición, animación ni rotación se usa el objeto base tal cual
+            Box::new(object)
+        } else if rotating || (animating && object_animation.is_some()) {
+            Box::new(MovingObject::new(object, transform_at))
+        } else {
+            Box::new(TransformedObject::new(object, transform_at(0.0)))
+        };
+        match editor.material(index) {
+            Some(material) => frame_objects.push(Box::new(MaterialOverride::new(frame_object, material.clone()))),
+            None if tuned => frame_objects.push(Box::new(Retuned::new(frame_object, editor))),
+            None => frame_objects.push(frame_object),
+        }
     }
-    
-    rotated_objects
+
+    frame_objects
 }
 
 fn main() {
     let window_width = 1300;
     let window_height = 900;
+
+    // Modo por lotes: `--sequence DIR ...` renderiza la animación a disco y sale
+    let args: Vec<String> = std::env::args().collect();
+    let sequence = match SequenceSettings::from_args(&args, window_width as u32, window_height as u32) {
+        Ok(sequence) => sequence,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
  
     let (mut window, thread) = raylib::init()
         .size(window_width, window_height)
@@ -516,8 +846,11 @@ fn main() {
     texture_manager.load_texture(&mut window, &thread, "assets/water_normal.png");
     texture_manager.load_texture(&mut window, &thread, "assets/lava_bubbles.png");
     texture_manager.load_texture(&mut window, &thread, "assets/lava_normal.png");
+    texture_manager.load_texture(&mut window, &thread, "assets/terrain_height.png");
     
     let mut framebuffer = Framebuffer::new(window_width as u32, window_height as u32);
+    let ray_counters = RayCounters::default(); // Rayos trazados desde el último frame
+    let mut hud = Hud::new(); // Estadísticas de render (I)
 
     // ========== CREAR SKYBOX ==========
     // Skybox atmosférico con atardecer (puedes cambiar por otros presets)
@@ -525,56 +858,139 @@ fn main() {
     
     // Materiales temáticos usando las texturas disponibles
     let tierra_hierba = Material::tierra_hierba(); // Ya tiene las rutas correctas
-    let piedra_castillo = Material::piedra_castillo(); // Ya tiene las rutas correctas
+    let mut piedra_castillo = Material::piedra_castillo(); // Ya tiene las rutas correctas
     let agua = Material::agua(); // Ya tiene las rutas correctas
     let lava = Material::lava(); // Ya tiene las rutas correctas
-    let cristal_blanco = Material::cristal_gema();
-    let cristal_esmeralda = Material::cristal_esmeralda();
-    let cristal_rubi = Material::cristal_rubi();
-    let cristal_zafiro = Material::cristal_zafiro();
+    let mut cristal_blanco = Material::cristal_gema();
+    let mut cristal_esmeralda = Material::cristal_esmeralda();
+    let mut cristal_rubi = Material::cristal_rubi();
+    let mut cristal_zafiro = Material::cristal_zafiro();
     
     // Nuevos materiales para elementos del diorama
     let madera = Material::madera();
     let hojas = Material::hojas();
-    let piedra_oscura = Material::piedra_oscura();
+    let mut piedra_oscura = Material::piedra_oscura();
+
+    // Variantes con texturas procedurales (en espacio de objeto: no "nadan" al rotar la escena)
+    let mut madera = madera
+        .with_procedural_color(
+            ProceduralTexture::new(NoisePattern::Turbulence, 3.0)
+                .with_warp(0.8)
+                .in_object_space()
+                .with_colors(Vector3::new(0.35, 0.2, 0.1), Vector3::new(0.7, 0.5, 0.3)),
+        )
+        .with_bump(ProceduralTexture::new(NoisePattern::Perlin, 12.0).in_object_space(), 0.004); // Veta de la madera
+    let mut hojas = hojas
+        .with_procedural_color(
+            ProceduralTexture::new(NoisePattern::Simplex, 4.0)
+                .in_object_space()
+                .with_colors(Vector3::new(0.1, 0.45, 0.15), Vector3::new(0.35, 0.85, 0.3)),
+        )
+        .with_roughness(ProceduralTexture::new(NoisePattern::Worley, 8.0).in_object_space()); // Follaje moteado
+    let piedra_agrietada = piedra_oscura
+        .clone()
+        .with_bump(ProceduralTexture::new(NoisePattern::WorleyEdges, 5.0).with_seed(3).in_object_space(), 0.01)
+        .with_roughness(ProceduralTexture::new(NoisePattern::Fbm, 3.0).with_octaves(5)); // Ruinas agrietadas
+
+    // Materiales compuestos con el grafo de nodos
+    // Nieve en las caras que miran hacia arriba de las cumbres; la cota de nieve ondula con ruido
+    let cota_nieve = ShaderNode::add(
+        ShaderNode::Height,
+        ShaderNode::multiply(ShaderNode::noise(ProceduralTexture::new(NoisePattern::Fbm, 1.5).with_seed(7)), ShaderNode::constant(0.8)),
+    );
+    let mascara_nieve = ShaderNode::multiply(
+        ShaderNode::smoothstep(0.75, 0.9, ShaderNode::upness()),
+        ShaderNode::smoothstep(1.0, 1.6, cota_nieve),
+    );
+    let mut tierra_hierba = tierra_hierba
+        .with_node(MaterialInput::Color, ShaderNode::mix(ShaderNode::BaseColor, ShaderNode::color(Vector3::new(0.95, 0.97, 1.0)), mascara_nieve.clone()))
+        .with_node(MaterialInput::Specular, ShaderNode::mix(ShaderNode::constant(10.0), ShaderNode::constant(60.0), mascara_nieve));
+    // Musgo en las piedras más bajas, junto al río, usando la hierba teñida como color
+    let musgo = ShaderNode::multiply(ShaderNode::texture("assets/grass_dirt.png"), ShaderNode::color(Vector3::new(0.5, 0.9, 0.4)));
+    let mascara_musgo = ShaderNode::multiply(
+        ShaderNode::smoothstep(-1.2, -1.8, ShaderNode::Height),
+        ShaderNode::smoothstep(-0.2, 0.6, ShaderNode::upness()),
+    );
+    let mut piedra_agrietada = piedra_agrietada.with_node(MaterialInput::Color, ShaderNode::mix(ShaderNode::BaseColor, musgo, mascara_musgo));
+    // Agua con efecto Fresnel: refleja más al mirarla de canto y deja ver el fondo de frente
+    let mut agua = agua
+        .with_uv_flow(0.0, 0.12) // Corriente del río
+        .with_node(MaterialInput::Reflectivity, ShaderNode::mix(ShaderNode::constant(0.85), ShaderNode::constant(0.2), ShaderNode::Facing))
+        .with_node(MaterialInput::Transparency, ShaderNode::mix(ShaderNode::constant(0.1), ShaderNode::constant(0.7), ShaderNode::Facing));
+    // Costra de lava enfriada: más brillante que la lava fundida, arrastrada por la corriente;
+    // la lava además late con un brillo propio
+    let costra = ProceduralTexture::new(NoisePattern::Worley, 3.0)
+        .in_object_space()
+        .with_velocity(Vector3::new(0.0, -0.15, 0.05));
+    let mut lava = lava
+        .with_uv_flow(0.02, 0.05)
+        .with_node(
+            MaterialInput::Specular,
+            ShaderNode::add(ShaderNode::constant(10.0), ShaderNode::multiply(ShaderNode::noise(costra), ShaderNode::constant(50.0))),
+        )
+        .with_node(
+            MaterialInput::Emission,
+            ShaderNode::multiply(
+                ShaderNode::color(Vector3::new(0.6, 0.18, 0.02)),
+                ShaderNode::add(ShaderNode::constant(0.2), ShaderNode::multiply(ShaderNode::pulse(0.4), ShaderNode::constant(0.3))),
+            ),
+        );
+
+    // Cristal del cielo: parpadea entre blanco y azul (keyframes escalonados)
+    let mut cristal_cielo = cristal_blanco.clone().with_node(
+        MaterialInput::Color,
+        ShaderNode::keyframes(
+            Track::new()
+                .key(0.0, Vector3::new(0.9, 0.9, 1.0), Interpolation::Step)
+                .key(1.5, Vector3::new(0.4, 0.6, 1.0), Interpolation::Step)
+                .key(3.0, Vector3::new(0.9, 0.9, 1.0), Interpolation::Step)
+                .looped(),
+        ),
+    );
+
+    // Grafos escritos como texto en material_graphs.txt: se conectan al arrancar,
+    // sin recompilar, y sustituyen a la entrada que se haya conectado aquí arriba
+    let material_graphs_path = std::path::Path::new("material_graphs.txt");
+    match shader_graph::load(material_graphs_path) {
+        Ok(connections) => {
+            for GraphConnection { material, input, node } in connections {
+                let target = match material.as_str() {
+                    "tierra_hierba" => &mut tierra_hierba,
+                    "piedra_castillo" => &mut piedra_castillo,
+                    "piedra_oscura" => &mut piedra_oscura,
+                    "piedra_agrietada" => &mut piedra_agrietada,
+                    "madera" => &mut madera,
+                    "hojas" => &mut hojas,
+                    "agua" => &mut agua,
+                    "lava" => &mut lava,
+                    "cristal_blanco" => &mut cristal_blanco,
+                    "cristal_esmeralda" => &mut cristal_esmeralda,
+                    "cristal_rubi" => &mut cristal_rubi,
+                    "cristal_zafiro" => &mut cristal_zafiro,
+                    "cristal_cielo" => &mut cristal_cielo,
+                    _ => {
+                        eprintln!("{}: Unknown material '{}'", material_graphs_path.display(), material);
+                        continue;
+                    }
+                };
+                *target = target.clone().with_node(input, node);
+            }
+        }
+        Err(error) => eprintln!("{}", error),
+    }
+
+    // Materiales que cambian con el tiempo: si no hay ninguno (ni nada animado),
+    // reproducir el tiempo no interrumpe el refinado de la imagen
+    let animated_materials = [
+        &tierra_hierba, &piedra_castillo, &piedra_oscura, &piedra_agrietada, &madera, &hojas, &agua, &lava,
+        &cristal_blanco, &cristal_esmeralda, &cristal_rubi, &cristal_zafiro, &cristal_cielo,
+    ]
+    .into_iter()
+    .any(Material::is_animated);
 
     // Crear un diorama de terreno flotante con cuadrícula 5x5
-    let base_objects = [
-        // ========== TERRENO BASE - CUADRÍCULA 5x5 ==========
-        // Fila trasera (Z = -4) - Elevación alta para montañas
-        Cube::new(Vector3::new(-4.0, -1.0, -4.0), 2.0, tierra_hierba.clone()), // Esquina noroeste
-        Cube::new(Vector3::new(-2.0, 0.0, -4.0), 2.0, tierra_hierba.clone()),  // Elevación media
-        Cube::new(Vector3::new(0.0, 1.0, -4.0), 2.0, tierra_hierba.clone()),   // Pico montañoso
-        Cube::new(Vector3::new(2.0, 0.5, -4.0), 2.0, tierra_hierba.clone()),   // Descendiendo
-        Cube::new(Vector3::new(4.0, -0.5, -4.0), 2.0, tierra_hierba.clone()),  // Esquina noreste
-        
-        // Fila medio-trasera (Z = -2) - Transición de montaña a valle
-        Cube::new(Vector3::new(-4.0, -1.5, -2.0), 2.0, tierra_hierba.clone()), // Ladera oeste
-        Cube::new(Vector3::new(-2.0, -1.0, -2.0), 2.0, tierra_hierba.clone()), // Valle intermedio
-        Cube::new(Vector3::new(0.0, 0.0, -2.0), 2.0, tierra_hierba.clone()),   // Planicie central
-        Cube::new(Vector3::new(2.0, -0.5, -2.0), 2.0, tierra_hierba.clone()),  // Inicio descenso
-        Cube::new(Vector3::new(4.0, -1.0, -2.0), 2.0, tierra_hierba.clone()),  // Ladera este
-        
-        // Fila central (Z = 0) - Nivel principal del diorama
-        Cube::new(Vector3::new(-4.0, -2.0, 0.0), 2.0, tierra_hierba.clone()),  // Nivel bajo oeste
-        Cube::new(Vector3::new(-2.0, -1.5, 0.0), 2.0, tierra_hierba.clone()),  // Río: orilla oeste
-        // [ESPACIO PARA RÍO] Vector3::new(0.0, -3.0, 0.0) - Cauce del río
-        Cube::new(Vector3::new(2.0, -1.5, 0.0), 2.0, tierra_hierba.clone()),   // Río: orilla este
-        Cube::new(Vector3::new(4.0, -2.0, 0.0), 2.0, tierra_hierba.clone()),   // Nivel bajo este
-        
-        // Fila medio-frontal (Z = 2) - Área de cascada
-        Cube::new(Vector3::new(-4.0, -2.5, 2.0), 2.0, tierra_hierba.clone()),  // Terraza baja
-        Cube::new(Vector3::new(-2.0, -2.0, 2.0), 2.0, tierra_hierba.clone()),  // Cascada: nivel alto
-        Cube::new(Vector3::new(0.0, -3.5, 2.0), 2.0, tierra_hierba.clone()),   // Piscina de cascada
-        Cube::new(Vector3::new(2.0, -2.0, 2.0), 2.0, tierra_hierba.clone()),   // Terraza este
-        Cube::new(Vector3::new(4.0, -2.5, 2.0), 2.0, tierra_hierba.clone()),   // Borde sur-este
-        
-        // Fila frontal (Z = 4) - Nivel más bajo
-        Cube::new(Vector3::new(-4.0, -3.0, 4.0), 2.0, tierra_hierba.clone()),  // Base suroeste
-        Cube::new(Vector3::new(-2.0, -3.0, 4.0), 2.0, tierra_hierba.clone()),  // Valle sur
-        Cube::new(Vector3::new(0.0, -4.0, 4.0), 2.0, tierra_hierba.clone()),   // Punto más bajo
-        Cube::new(Vector3::new(2.0, -3.0, 4.0), 2.0, tierra_hierba.clone()),   // Valle sur-este
-        Cube::new(Vector3::new(4.0, -3.0, 4.0), 2.0, tierra_hierba.clone()),   // Base sureste
+    let base_cubes = [
+        // El terreno base es un heightfield (ver más abajo), no cubos
         
         // ========== SISTEMA DE AGUA COMPLEJO - RÍO Y CASCADA ==========
         // Nacimiento del río (manantial en las montañas)
@@ -609,9 +1025,7 @@ fn main() {
         Cube::new(Vector3::new(1.8, -1.9, 0.8), 0.7, agua.clone()),     // Afluente este 1
         Cube::new(Vector3::new(1.3, -2.4, 0.3), 0.8, agua.clone()),     // Confluencia este
         
-        // Lagos y pozas adicionales
-        Cube::new(Vector3::new(-2.5, -2.8, 1.2), 1.0, agua.clone()),    // Lago oeste
-        Cube::new(Vector3::new(2.8, -3.2, 1.8), 1.1, agua.clone()),     // Lago este
+        // Lagos y pozas adicionales (los lagos son quads, ver más abajo)
         Cube::new(Vector3::new(-1.2, -4.8, 3.8), 0.9, agua.clone()),    // Poza de remanso oeste
         Cube::new(Vector3::new(1.5, -5.0, 4.2), 0.8, agua.clone()),     // Poza de remanso este
         
@@ -629,13 +1043,9 @@ fn main() {
         // Murallas del castillo (sobre terreno base)
         Cube::new(Vector3::new(-1.5, 1.5, -3.5), 1.0, piedra_castillo.clone()), // Muralla oeste (sobre Y=0.0 + 1.5)
         Cube::new(Vector3::new(1.5, 1.5, -3.5), 1.0, piedra_castillo.clone()),  // Muralla este
-        Cube::new(Vector3::new(0.0, 1.0, -3.0), 1.5, piedra_castillo.clone()),  // Muralla frontal
+        // Muralla frontal: tallada con CSG (puerta en arco, ver más abajo)
         
-        // Torres de las esquinas (con bases sólidas)
-        Cube::new(Vector3::new(-2.0, 0.8, -3.0), 1.2, piedra_castillo.clone()), // Base torre suroeste
-        Cube::new(Vector3::new(-2.0, 2.0, -3.0), 1.0, piedra_castillo.clone()), // Torre suroeste
-        Cube::new(Vector3::new(2.0, 0.8, -3.0), 1.2, piedra_castillo.clone()),  // Base torre sureste
-        Cube::new(Vector3::new(2.0, 2.0, -3.0), 1.0, piedra_castillo.clone()),  // Torre sureste
+        // Torres de las esquinas: cilindros con tejado cónico (ver primitivas redondas)
         
         // Puertas y accesos (a nivel del suelo)
         Cube::new(Vector3::new(-0.8, 0.5, -2.8), 0.6, piedra_castillo.clone()), // Entrada oeste
@@ -643,43 +1053,32 @@ fn main() {
         
         // ========== RUINAS ANTIGUAS (SOBRE TERRENO) ==========
         // Ruinas en el lado oeste (sobre ladera Y=-1.5)
-        Cube::new(Vector3::new(-3.5, -1.0, -1.0), 0.8, piedra_oscura.clone()), // Pilar en ruinas (sobre terreno)
-        Cube::new(Vector3::new(-3.2, -0.5, -0.8), 0.6, piedra_oscura.clone()), // Fragmento superior
-        Cube::new(Vector3::new(-4.0, -1.3, -0.5), 0.7, piedra_oscura.clone()), // Base de ruina (sobre terreno Y=-2.0)
-        Cube::new(Vector3::new(-3.8, -0.8, 0.2), 0.5, piedra_oscura.clone()),  // Fragmento caído
+        // Pilar en ruinas: intersección CSG (ver más abajo)
+        Cube::new(Vector3::new(-3.2, -0.5, -0.8), 0.6, piedra_agrietada.clone()), // Fragmento superior
+        Cube::new(Vector3::new(-4.0, -1.3, -0.5), 0.7, piedra_agrietada.clone()), // Base de ruina (sobre terreno Y=-2.0)
+        Cube::new(Vector3::new(-3.8, -0.8, 0.2), 0.5, piedra_agrietada.clone()),  // Fragmento caído
         
-        // Ruinas junto al río (sobre terreno Y=-2.5)
-        Cube::new(Vector3::new(-2.5, -2.3, 1.5), 0.7, piedra_oscura.clone()),  // Ruina sobre terraza
-        Cube::new(Vector3::new(-2.2, -2.0, 1.8), 0.4, piedra_oscura.clone()),  // Fragmento pequeño
+        // Ruinas junto al río: superficie implícita erosionada (ver SDF más abajo)
         
         // ========== BOSQUE Y ÁRBOLES (PLANTADOS EN TERRENO) ==========
+        // Los troncos son cilindros y cápsulas (ver primitivas redondas)
         // Árbol grande en la ladera oeste (sobre terreno Y=-1.5)
-        Cube::new(Vector3::new(-3.5, -1.3, 0.5), 0.4, madera.clone()),   // Tronco base (plantado en terreno)
-        Cube::new(Vector3::new(-3.5, -0.9, 0.5), 0.4, madera.clone()),   // Tronco medio
-        Cube::new(Vector3::new(-3.5, -0.5, 0.5), 0.3, madera.clone()),   // Tronco superior
         Cube::new(Vector3::new(-3.5, -0.1, 0.5), 1.2, hojas.clone()),    // Copa del árbol
         Cube::new(Vector3::new(-3.2, 0.1, 0.8), 0.8, hojas.clone()),     // Rama este
         Cube::new(Vector3::new(-3.8, 0.1, 0.2), 0.8, hojas.clone()),     // Rama oeste
         
         // Grupo de árboles pequeños (sobre terraza Y=-2.5)
-        Cube::new(Vector3::new(-2.8, -2.3, 1.8), 0.3, madera.clone()),   // Tronco 1 (plantado)
         Cube::new(Vector3::new(-2.8, -1.8, 1.8), 0.7, hojas.clone()),    // Copa 1
-        Cube::new(Vector3::new(-2.2, -2.4, 2.2), 0.25, madera.clone()),  // Tronco 2 (plantado)
         Cube::new(Vector3::new(-2.2, -2.0, 2.2), 0.6, hojas.clone()),    // Copa 2
-        Cube::new(Vector3::new(-2.5, -2.2, 2.5), 0.3, madera.clone()),   // Tronco 3 (plantado)
         Cube::new(Vector3::new(-2.5, -1.7, 2.5), 0.8, hojas.clone()),    // Copa 3
         
         // Árbol junto al río (sobre orilla Y=-1.5)
-        Cube::new(Vector3::new(1.8, -1.3, 0.2), 0.35, madera.clone()),   // Tronco sauce (plantado)
-        Cube::new(Vector3::new(1.8, -0.9, 0.2), 0.3, madera.clone()),    // Tronco medio
         Cube::new(Vector3::new(1.8, -0.5, 0.2), 1.0, hojas.clone()),     // Copa sauce
         Cube::new(Vector3::new(1.5, -0.7, 0.5), 0.7, hojas.clone()),     // Ramas colgantes
         Cube::new(Vector3::new(2.1, -0.8, -0.1), 0.6, hojas.clone()),    // Más ramas
         
         // Árboles en las montañas (sobre elevación Y=0.5)
-        Cube::new(Vector3::new(2.2, 0.7, -3.5), 0.3, madera.clone()),    // Tronco montaña (plantado)
         Cube::new(Vector3::new(2.2, 1.3, -3.5), 0.9, hojas.clone()),     // Copa montaña
-        Cube::new(Vector3::new(-1.8, 0.2, -3.2), 0.25, madera.clone()),  // Tronco pequeño (plantado)
         Cube::new(Vector3::new(-1.8, 0.6, -3.2), 0.7, hojas.clone()),    // Copa pequeña
         
         // ========== SISTEMA DE LAVA COMPLEJO ==========
@@ -736,7 +1135,6 @@ fn main() {
         // Área donde lava y agua se encuentran (vapor y efectos)
         Cube::new(Vector3::new(1.8, -4.5, 2.5), 0.6, agua.clone()),     // Agua resistiendo lava
         Cube::new(Vector3::new(2.2, -4.3, 2.3), 0.5, lava.clone()),     // Lava encuentro agua
-        Cube::new(Vector3::new(2.0, -4.0, 2.4), 0.4, cristal_blanco.clone()), // Vapor/cristalización
         
         // Zona de batalla termal
         Cube::new(Vector3::new(1.5, -4.8, 3.0), 0.7, agua.clone()),     // Agua defendiendo
@@ -756,14 +1154,9 @@ fn main() {
         Cube::new(Vector3::new(3.6, 1.0, -3.8), 0.2, lava.clone()),     // Proyectil lava 1
         Cube::new(Vector3::new(4.1, 1.5, -3.0), 0.2, lava.clone()),     // Proyectil lava 2
         
-        // Estanques de reflexión perfecta (agua muy tranquila)
-        Cube::new(Vector3::new(-3.5, -2.5, 0.5), 1.0, agua.clone()),    // Estanque espejo oeste
-        Cube::new(Vector3::new(3.2, -4.2, 4.0), 1.2, agua.clone()),     // Estanque espejo este
-        
         // Fuentes termales (donde lava calienta agua subterránea)
         Cube::new(Vector3::new(1.8, -3.8, -0.8), 0.8, agua.clone()),    // Fuente termal 1
         Cube::new(Vector3::new(2.5, -3.5, -1.2), 0.7, agua.clone()),    // Fuente termal 2
-        Cube::new(Vector3::new(2.1, -3.2, -1.0), 0.4, cristal_blanco.clone()), // Vapor termal
         
         
         // ========== FORMACIONES CRISTALINAS MEJORADAS ==========
@@ -790,8 +1183,6 @@ fn main() {
         Cube::new(Vector3::new(-3.0, 4.0, -1.0), 0.6, cristal_esmeralda.clone()), // Cristal del bosque (más bajo)
         Cube::new(Vector3::new(3.0, 2.5, 1.0), 0.5, cristal_rubi.clone()),        // Cristal del fuego
         Cube::new(Vector3::new(-1.0, 3.5, 3.0), 0.7, cristal_zafiro.clone()),     // Cristal del agua
-        Cube::new(Vector3::new(0.0, 6.0, -2.0), 0.4, cristal_blanco.clone()),     // Cristal del cielo (reducido)
-        Cube::new(Vector3::new(2.5, 4.5, 0.5), 0.5, cristal_esmeralda.clone()),   // Cristal errante
         
         // Cristales mágicos adicionales (flotantes pero más bajos)
         Cube::new(Vector3::new(-1.5, 5.0, 1.0), 0.3, cristal_rubi.clone()),       // Cristal rubí flotante
@@ -799,6 +1190,131 @@ fn main() {
         Cube::new(Vector3::new(-0.5, 4.8, 2.5), 0.35, cristal_blanco.clone()),    // Cristal puro flotante
     ];
 
+    // Todos los objetos de la escena comparten la interfaz RayIntersect
+    let mut base_objects: Vec<Box<dyn RayIntersect>> = Vec::with_capacity(base_cubes.len() + 20);
+
+    // ========== TERRENO BASE - HEIGHTFIELD ==========
+    // Mapa de alturas con las mismas cotas que la antigua cuadrícula 5x5 de cubos,
+    // interpolado suavemente; la hierba se repite cada 2 unidades como en los cubos.
+    match Heightfield::from_texture(
+        &texture_manager,
+        "assets/terrain_height.png",
+        Vector3::new(-5.0, -5.0, -5.0),
+        Vector3::new(5.0, 2.5, 5.0),
+        tierra_hierba.clone(),
+    ) {
+        Ok(terreno) => base_objects.push(Box::new(terreno.with_tiling(5.0, 5.0))),
+        Err(error) => eprintln!("{}", error),
+    }
+
+    for cube in base_cubes {
+        base_objects.push(Box::new(cube));
+    }
+
+    // Cristales animados con keyframes (ver ANIMACIÓN más abajo): se guarda su índice
+    let cristal_cielo_index = base_objects.len();
+    base_objects.push(Box::new(Cube::new(Vector3::new(0.0, 6.0, -2.0), 0.4, cristal_cielo.clone())));  // Cristal del cielo (reducido)
+    let cristal_errante_index = base_objects.len();
+    base_objects.push(Box::new(Cube::new(Vector3::new(2.5, 4.5, 0.5), 0.5, cristal_esmeralda.clone())));  // Cristal errante
+
+    // ========== SUPERFICIES PLANAS (UN SOLO OBJETO CADA UNA) ==========
+    let up = Vector3::new(0.0, 1.0, 0.0);
+
+    // Lagos: quads horizontales en lugar de cubos de agua (ondas más finas con tiling 2x2)
+    base_objects.push(Box::new(Quad::horizontal(Vector3::new(-2.5, -2.3, 1.2), 1.0, 1.0, agua.clone()).with_tiling(2.0, 2.0)));  // Lago oeste
+    base_objects.push(Box::new(Quad::horizontal(Vector3::new(2.8, -2.65, 1.8), 1.1, 1.1, agua.clone()).with_tiling(2.0, 2.0)));  // Lago este
+
+    // Estanques de reflexión perfecta (agua muy tranquila): discos planos
+    base_objects.push(Box::new(Disk::new(Vector3::new(-3.5, -2.0, 0.5), up, 0.5, agua.clone()).with_tiling(2.0, 2.0)));  // Estanque espejo oeste
+    base_objects.push(Box::new(Disk::new(Vector3::new(3.2, -3.6, 4.0), up, 0.6, agua.clone()).with_tiling(2.0, 2.0)));   // Estanque espejo este
+
+    // ========== PRIMITIVAS REDONDAS (TRONCOS Y TORRES) ==========
+    // Troncos de madera: cilindros y cápsulas en lugar de cubos apilados
+    base_objects.push(Box::new(Cylinder::vertical(Vector3::new(-3.5, -1.5, 0.5), 0.18, 1.15, madera.clone())));    // Árbol grande
+    base_objects.push(Box::new(Capsule::new(Vector3::new(-2.8, -2.35, 1.8), Vector3::new(-2.8, -2.1, 1.8), 0.1, madera.clone())));    // Tronco 1
+    base_objects.push(Box::new(Capsule::new(Vector3::new(-2.2, -2.445, 2.2), Vector3::new(-2.2, -2.2, 2.2), 0.08, madera.clone())));  // Tronco 2
+    base_objects.push(Box::new(Capsule::new(Vector3::new(-2.5, -2.25, 2.5), Vector3::new(-2.5, -1.95, 2.5), 0.1, madera.clone())));   // Tronco 3
+    base_objects.push(Box::new(Cylinder::vertical(Vector3::new(1.8, -1.475, 0.2), 0.15, 0.725, madera.clone())));  // Tronco sauce
+    base_objects.push(Box::new(Cylinder::vertical(Vector3::new(2.2, 0.55, -3.5), 0.12, 0.3, madera.clone())));     // Tronco montaña
+    base_objects.push(Box::new(Cylinder::vertical(Vector3::new(-1.8, 0.075, -3.2), 0.1, 0.25, madera.clone())));   // Tronco pequeño
+
+    // Torres de las esquinas del castillo con tejados cónicos
+    base_objects.push(Box::new(Cylinder::vertical(Vector3::new(-2.0, 0.2, -3.0), 0.55, 2.3, piedra_castillo.clone())));  // Torre suroeste
+    base_objects.push(Box::new(Cone::vertical(Vector3::new(-2.0, 2.5, -3.0), 0.7, 0.8, piedra_oscura.clone())));         // Tejado suroeste
+    base_objects.push(Box::new(Cylinder::vertical(Vector3::new(2.0, 0.2, -3.0), 0.55, 2.3, piedra_castillo.clone())));   // Torre sureste
+    base_objects.push(Box::new(Cone::vertical(Vector3::new(2.0, 2.5, -3.0), 0.7, 0.8, piedra_oscura.clone())));          // Tejado sureste
+    base_objects.push(Box::new(Cone::vertical(Vector3::new(0.0, 5.4, -4.0), 0.55, 0.9, piedra_oscura.clone())));         // Tejado torre principal
+
+    // ========== GEOMETRÍA CONSTRUCTIVA (CSG) ==========
+    // Muralla frontal con una puerta en arco: cubo menos (cubo ∪ cilindro horizontal)
+    // El hueco atraviesa todo el grosor del muro (z de -3.75 a -2.25) con margen;
+    // los cubos no se estiran, así que el paso recto es una fila de cubos solapados
+    let mut doorway = Csg::union(
+        Cube::new(Vector3::new(0.0, 0.45, -2.4), 0.5, piedra_oscura.clone()),
+        Cylinder::new(Vector3::new(0.0, 0.7, -3.85), Vector3::new(0.0, 0.0, 1.0), 0.25, 1.7, piedra_oscura.clone()),
+    );
+    for z in [-2.85, -3.3, -3.6] {
+        doorway = Csg::union(doorway, Cube::new(Vector3::new(0.0, 0.45, z), 0.5, piedra_oscura.clone()));
+    }
+    base_objects.push(Box::new(Csg::difference(
+        Cube::new(Vector3::new(0.0, 1.0, -3.0), 1.5, piedra_castillo.clone()),
+        doorway,
+    )));  // Muralla frontal
+
+    // Pilar en ruinas con aristas desgastadas: cubo ∩ cilindro, partido en diagonal
+    // por un plano (el semiespacio bajo él)
+    let pilar = Csg::intersection(
+        Cube::new(Vector3::new(-3.5, -1.0, -1.0), 0.8, piedra_agrietada.clone()),
+        Cylinder::vertical(Vector3::new(-3.5, -1.4, -1.0), 0.48, 0.8, piedra_agrietada.clone()),
+    );
+    let fractura = Plane::new(Vector3::new(-3.5, -0.8, -1.0), Vector3::new(0.5, 1.0, 0.3), piedra_agrietada.clone()).with_tiling(1.25, 1.25);
+    base_objects.push(Box::new(Csg::intersection(pilar, fractura)));  // Pilar en ruinas
+
+    // ========== SUPERFICIES IMPLÍCITAS (SDF) ==========
+    // Ruinas junto al río: bloques redondeados, mordidos por la erosión y ondulados
+    let ruinas_rio = SdfShape::round_box(Vector3::new(-2.5, -2.3, 1.5), Vector3::new(0.35, 0.35, 0.35), 0.05)   // Ruina sobre terraza
+        .union(SdfShape::round_box(Vector3::new(-2.2, -2.0, 1.8), Vector3::new(0.2, 0.2, 0.2), 0.04))         // Fragmento pequeño
+        .smooth_subtraction(SdfShape::sphere(Vector3::new(-2.2, -1.95, 1.25), 0.25), 0.08)                    // Hueco erosionado
+        .displace(0.015, 18.0);
+    base_objects.push(Box::new(SdfObject::new(ruinas_rio, piedra_agrietada.clone())));
+
+    // Borde rocoso del estanque espejo oeste: anillo fundido suavemente con una roca achatada
+    let roca = SdfShape::sphere(Vector3::new(-3.05, -1.95, 0.7), 0.2)
+        .smooth_intersection(SdfShape::round_box(Vector3::new(-3.05, -2.0, 0.7), Vector3::new(0.3, 0.1, 0.3), 0.02), 0.05);
+    let borde_estanque = SdfShape::torus(Vector3::new(-3.5, -2.0, 0.5), 0.5, 0.07)
+        .smooth_union(roca, 0.12);
+    base_objects.push(Box::new(SdfObject::new(borde_estanque, piedra_oscura.clone())));
+
+    // ========== VÓXELES (CUADRÍCULA DISPERSA) ==========
+    // Puente de madera sobre el río: tablones y barandillas de piedra vóxel a vóxel
+    let mut puente = VoxelGrid::new(Vector3::new(-1.6, -0.95, -1.0), 0.1, 32, 8, 8);
+    let tablon = puente.add_material(madera.clone());
+    let baranda = puente.add_material(piedra_oscura.clone());
+    puente.fill_box((0, 4, 1), (31, 4, 6), tablon);        // Tablero
+    puente.fill_box((0, 0, 1), (2, 3, 6), baranda);         // Estribo oeste
+    puente.fill_box((29, 0, 1), (31, 3, 6), baranda);       // Estribo este
+    for x in (0..32).step_by(4) {
+        puente.fill_box((x, 5, 0), (x, 6, 0), baranda);     // Postes norte
+        puente.fill_box((x, 5, 7), (x, 6, 7), baranda);     // Postes sur
+    }
+    puente.fill_box((0, 7, 0), (31, 7, 0), baranda);        // Pasamanos norte
+    puente.fill_box((0, 7, 7), (31, 7, 7), baranda);        // Pasamanos sur
+    base_objects.push(Box::new(puente));
+
+    // Santuario importado de MagicaVoxel en la terraza suroeste
+    match VoxelGrid::from_vox("assets/shrine.vox", Vector3::new(-4.2, -2.1, 3.2), 0.12, &piedra_castillo) {
+        Ok(santuario) => base_objects.push(Box::new(santuario)),
+        Err(error) => eprintln!("{}", error),
+    }
+
+    // ========== EDICIÓN DE ESCENA ==========
+    // Cambios hechos en la app sobre la escena generada, guardados en cada edición
+    let scene_edits_path = std::path::Path::new("scene_edits.txt");
+    let mut editor = SceneEditor::new(base_objects.len());
+    if let Err(error) = editor.load(scene_edits_path, &mut base_objects) {
+        eprintln!("{}", error);
+    }
+
     // ========== SISTEMA DE ROTACIÓN GLOBAL DE ESCENA ==========
     let mut scene_rotation_angle = 0.0f32;
     let mut scene_rotation_speed = 0.0f32; // Radianes por frame
@@ -816,11 +1332,36 @@ fn main() {
         Vector3::new(0.0, 0.0, 15.0),   // Posición frontal: centrada en X, elevada en Y, alejada en Z
         Vector3::new(0.0, 0.0, 0.0),    // Mirar al centro del diorama
         Vector3::new(0.0, 1.0, 0.0),    // Vector up estándar
-    );
+    )
+    .with_lens(0.0, 15.0); // Lente cerrada (Z/X la abren), enfocada en el centro del diorama
     let rotation_speed = PI / 100.0;
+    let mouse_sensitivity = 0.003f32; // Radianes por píxel arrastrado
+    let wheel_zoom_step = 0.04f32; // Fracción de la distancia al centro por paso de rueda
+    let zoom_damping = 0.75f32; // Impulso de zoom que queda tras cada frame
+    let mut zoom_velocity = 0.0f32;
+    let click_tolerance = 4.0f32; // Píxeles de arrastre que aún cuentan como clic
+    let double_click_time = 0.35f64; // Segundos entre clics de un doble clic
+    let mut left_drag_distance = 0.0f32;
+    let mut right_drag_distance = 0.0f32;
+    let mut selection: Option<Pick> = None;
+    let inspector_width = 380; // Píxeles reservados al panel del inspector
+    let mut last_click_time = f64::NEG_INFINITY;
+    let mut free_fly = false;
+    let mut camera_collisions = false;
+    let camera_clearance = 0.3f32; // Distancia mínima del ojo a cualquier superficie
+    let fov_step = 0.01f32; // Radianes por frame con - / = pulsadas
+    let aperture_step = 0.005f32; // Radio de lente por frame con Z/X pulsadas
+    let mut primitive_kind = PrimitiveKind::Cube; // Primitiva que añade O
+    let mut object_grabbed = false; // Gesto de Ctrl en curso que ya guardó su paso de deshacer
+    let mut material_editor_open = false;
+    let mut material_drag: Option<usize> = None; // Fila del editor de materiales agarrada con el ratón
+    let mut status_message: Option<(String, f64)> = None; // Último aviso y cuándo se dio
+    let mut material_grabbed = false; // Arrastre de un deslizador que ya guardó su paso de deshacer
+    let materials_export_path = std::path::Path::new("materials_export.rs");
 
     // Variables para renderizado progresivo e híbrido
     let mut current_sample = 0u32;
+    let mut accumulation = vec![Vector3::zero(); (window_width * window_height) as usize];
     let samples_per_frame = (window_width * window_height / 120) as u32; // Más conservador
     let mut render_complete = false;
     let mut frames_since_camera_change = 0u32;
@@ -828,31 +1369,656 @@ fn main() {
     let mut current_lod = 4u32; // Level of Detail inicial (más bajo = mejor calidad)
     let mut target_lod = 1u32;
 
-    let light = Light::new(
+    let mut light = Light::new(
         Vector3::new(5.0, 10.0, 5.0),
         Color::new(255, 255, 255, 255),
         2.0,
     );
 
+    // ========== ANIMACIÓN ==========
+    // Pistas de keyframes evaluadas cada frame con el tiempo de la escena
+    let animation = Animation::new()
+        .with_object(
+            // El cristal errante flota arriba y abajo mientras gira sobre sí mismo
+            ObjectAnimation::new(cristal_errante_index, Vector3::new(2.5, 4.5, 0.5))
+                .with_translation(
+                    Track::new()
+                        .key(0.0, Vector3::zero(), Interpolation::ease_in_out())
+                        .key(2.0, Vector3::new(0.0, 0.6, 0.0), Interpolation::ease_in_out())
+                        .key(4.0, Vector3::zero(), Interpolation::ease_in_out())
+                        .looped(),
+                )
+                .with_rotation(
+                    Track::new()
+                        .key(0.0, Vector3::zero(), Interpolation::Linear)
+                        .key(6.0, Vector3::new(0.0, 2.0 * PI, 0.0), Interpolation::Linear)
+                        .looped(),
+                ),
+        )
+        .with_object(
+            // El cristal del cielo late creciendo y encogiendo
+            ObjectAnimation::new(cristal_cielo_index, Vector3::new(0.0, 6.0, -2.0))
+                .with_scale(
+                    Track::new()
+                        .key(0.0, 1.0, Interpolation::ease_in_out())
+                        .key(1.5, 1.4, Interpolation::ease_in_out())
+                        .key(3.0, 1.0, Interpolation::ease_in_out())
+                        .looped(),
+                ),
+        )
+        .with_light(
+            // El sol oscila de un lado a otro y se enrojece en los extremos
+            LightAnimation::default()
+                .with_position(
+                    Track::new()
+                        .key(0.0, Vector3::new(5.0, 10.0, 5.0), Interpolation::ease_in_out())
+                        .key(15.0, Vector3::new(-5.0, 8.0, 5.0), Interpolation::ease_in_out())
+                        .key(30.0, Vector3::new(5.0, 10.0, 5.0), Interpolation::ease_in_out())
+                        .looped(),
+                )
+                .with_color(
+                    Track::new()
+                        .key(0.0, Vector3::new(1.0, 1.0, 1.0), Interpolation::Linear)
+                        .key(15.0, Vector3::new(1.0, 0.8, 0.6), Interpolation::Linear)
+                        .key(30.0, Vector3::new(1.0, 1.0, 1.0), Interpolation::Linear)
+                        .looped(),
+                )
+                .with_intensity(
+                    Track::new()
+                        .key(0.0, 2.0, Interpolation::Linear)
+                        .key(15.0, 1.6, Interpolation::Linear)
+                        .key(30.0, 2.0, Interpolation::Linear)
+                        .looped(),
+                ),
+        )
+        .with_camera(
+            // Vuelo alrededor del diorama (se activa con C)
+            CameraAnimation::default()
+                .with_eye(
+                    Track::new()
+                        .key(0.0, Vector3::new(0.0, 0.0, 15.0), Interpolation::ease_in_out())
+                        .key(8.0, Vector3::new(12.0, 4.0, 6.0), Interpolation::ease_in_out())
+                        .key(16.0, Vector3::new(0.0, 8.0, -12.0), Interpolation::ease_in_out())
+                        .key(24.0, Vector3::new(-12.0, 4.0, 6.0), Interpolation::ease_in_out())
+                        .key(32.0, Vector3::new(0.0, 0.0, 15.0), Interpolation::ease_in_out())
+                        .looped(),
+                )
+                .with_center(
+                    Track::new()
+                        .key(0.0, Vector3::zero(), Interpolation::Linear)
+                        .key(16.0, Vector3::new(0.0, 1.0, -2.0), Interpolation::Linear)
+                        .key(32.0, Vector3::zero(), Interpolation::Linear)
+                        .looped(),
+                ),
+        );
+    let mut camera_animated = false;
+
+    // ========== MARCADORES DE CÁMARA ==========
+    // Vistas guardadas en disco: F1-F8 las recuperan, Shift + F1-F8 las guardan
+    // y F9 recorre todas en bucle para presentaciones
+    let bookmarks_path = std::path::Path::new("camera_bookmarks.txt");
+    let mut bookmarks = bookmarks::load(bookmarks_path).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        Vec::new()
+    });
+    let bookmark_keys = [
+        KeyboardKey::KEY_F1, KeyboardKey::KEY_F2, KeyboardKey::KEY_F3, KeyboardKey::KEY_F4,
+        KeyboardKey::KEY_F5, KeyboardKey::KEY_F6, KeyboardKey::KEY_F7, KeyboardKey::KEY_F8,
+    ];
+    let bookmark_glide = 1.5f32; // Segundos del viaje al recuperar una vista
+    let (tour_travel, tour_hold) = (3.0f32, 1.5f32); // Segundos de viaje y de parada en la visita
+    let mut flythrough: Option<Flythrough> = None;
+
+    // ========== RECORRIDO DE CÁMARA (spline) ==========
+    // Puntos de control editables con K, Shift + K, coma/punto y Retroceso;
+    // J lo reproduce y H alterna Catmull-Rom / Bézier. Se guarda en cada cambio.
+    let camera_path_file = std::path::Path::new("camera_path.txt");
+    let mut camera_path = if camera_path_file.exists() {
+        CameraPath::load(camera_path_file).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            CameraPath::new(SplineKind::CatmullRom, 20.0).closed()
+        })
+    } else {
+        CameraPath::new(SplineKind::CatmullRom, 20.0).closed()
+    };
+    let mut path_selected = camera_path.len().checked_sub(1);
+    let mut path_playing = false;
+    let mut path_time = 0.0f32;
+
+    // ========== ATMÓSFERA ==========
+    // Vapor permanente sobre la zona donde se encuentran la lava y el agua;
+    // la niebla global se alterna con F (sin niebla -> uniforme -> de valle)
+    let vapor = FogVolume::new(Vector3::new(2.0, -3.7, 2.6), Vector3::new(1.4, 1.2, 1.2), 1.5, Vector3::new(0.9, 0.9, 0.95));
+    let mut fog_mode = 0u32;
+
+    // Medios heterogéneos: humo del volcán, vapor termal y bruma de la cascada
+    let humo_volcan = HeterogeneousVolume::new(
+        Vector3::new(4.0, 3.4, -4.0), Vector3::new(1.8, 3.2, 1.8), 3.0, Vector3::new(0.3, 0.3, 0.32),
+        DensitySource::Noise { scale: 2.5, octaves: 4, seed: 7 },
+    );
+    let vapor_termal = HeterogeneousVolume::new(
+        Vector3::new(2.1, -2.9, -1.0), Vector3::new(0.8, 1.2, 0.8), 4.0, Vector3::new(0.95, 0.95, 0.95),
+        DensitySource::Noise { scale: 4.0, octaves: 3, seed: 11 },
+    );
+    let mut media = vec![humo_volcan, vapor_termal];
+    match DensityGrid::from_raw("assets/mist_density.raw", 32, 16, 32) {
+        Ok(grid) => media.push(HeterogeneousVolume::new(
+            Vector3::new(0.0, -4.4, 2.9), Vector3::new(2.4, 1.2, 2.4), 2.5, Vector3::new(0.95, 0.97, 1.0),
+            DensitySource::Grid(grid),
+        )),
+        Err(error) => eprintln!("{}", error),
+    }
+    // ========== TIEMPO DE ESCENA ==========
+    // Reloj de la animación de materiales: T pausa/reanuda, [ y ] lo desplazan
+    let mut scene_time = 0.0f32;
+    let mut time_playing = false; // En pausa al arrancar: la imagen se refina del todo
+    let scrub_speed = 2.0f32; // Segundos de animación por segundo real
+
+    // ========== DESENFOQUE DE MOVIMIENTO (M) ==========
+    // El obturador queda abierto desde el frame anterior hasta el actual
+    let mut motion_blur = false;
+    let mut previous_time = scene_time;
+    let mut previous_rotation = 0.0f32;
+    let mut previous_camera = camera.clone();
+    // Tiempo, rotación y cámara al cerrar el último obturador
+    let mut closed_time = scene_time;
+    let mut closed_rotation = 0.0f32;
+    let mut closed_camera = camera.clone();
+
+    let base_atmosphere = |media: &[HeterogeneousVolume]| {
+        media.iter().cloned().fold(Atmosphere::new().with_volume(vapor.clone()), Atmosphere::with_medium)
+    };
+    let mut atmosphere = base_atmosphere(&media);
+
+    // ========== SECUENCIA OFFLINE ==========
+    // Cada frame se evalúa en instantes fijos de su obturador: el resultado no
+    // depende de la velocidad de la máquina ni de los frames ya renderizados
+    if let Some(sequence) = sequence {
+        // Vista de partida guardada con Shift + F1-F8 (la animación de cámara la sustituye)
+        let sequence_bookmark = match &sequence.bookmark {
+            Some(name) => match bookmarks.iter().flatten().find(|bookmark| &bookmark.name == name) {
+                Some(bookmark) => Some(bookmark.clone()),
+                None => {
+                    eprintln!("Unknown camera bookmark '{}' in {}", name, bookmarks_path.display());
+                    return;
+                }
+            },
+            None => None,
+        };
+        let sequence_path = match sequence.camera_path.as_deref().map(CameraPath::load).transpose() {
+            Ok(path) => path,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        let pending = sequence.pending_frames();
+        for (done, frame) in pending.iter().enumerate() {
+            if window.window_should_close() {
+                break;
+            }
+            let (open_time, close_time) = sequence.shutter_interval(*frame);
+            let shutter = Shutter {
+                open_time,
+                close_time,
+                open_rotation: sequence.turntable_angle(open_time),
+                close_rotation: sequence.turntable_angle(close_time),
+            };
+
+            let mut frame_light = light.clone();
+            if let Some(light_animation) = &animation.light {
+                light_animation.apply(&mut frame_light, open_time);
+            }
+            let mut frame_camera = camera.clone();
+            if let Some(bookmark) = &sequence_bookmark {
+                bookmark.apply(&mut frame_camera);
+            }
+            if let Some(projection) = sequence.projection {
+                frame_camera.set_projection(projection);
+            }
+            if let Some(fov) = sequence.fov {
+                frame_camera.set_fov(fov);
+            }
+            if let Some(aperture) = sequence.aperture {
+                frame_camera.set_aperture(aperture);
+            }
+            if let Some(focal_distance) = sequence.focal_distance {
+                frame_camera.set_focal_distance(focal_distance);
+            }
+            let (mut camera_open, mut camera_close) = (frame_camera.clone(), frame_camera);
+            if let Some(path) = &sequence_path {
+                path.apply(&mut camera_open, open_time);
+                path.apply(&mut camera_close, close_time);
+            } else if let Some(camera_animation) = animation.camera.as_ref().filter(|_| sequence.camera_animation) {
+                camera_animation.apply(&mut camera_open, open_time);
+                camera_animation.apply(&mut camera_close, close_time);
+            }
+
+            let frame_objects = create_frame_objects(&base_objects, &editor, shutter, &animation);
+            let scene = Scene {
+                objects: &frame_objects,
+                light: &frame_light,
+                texture_manager: &texture_manager,
+                skybox: &skybox,
+                atmosphere: &atmosphere,
+                time: open_time,
+                shutter: close_time - open_time,
+                camera_open: (close_time > open_time).then_some(&camera_open),
+                selected: None,
+                rays: &ray_counters,
+            };
+            let pixels = render_radiance(sequence.width, sequence.height, &scene, &camera_close, sequence.samples);
+            if let Err(error) = sequence.write_frame(*frame, &pixels) {
+                eprintln!("{}", error);
+                return;
+            }
+            println!("Frame {} ({}/{})", frame, done + 1, pending.len());
+        }
+        return;
+    }
+
     while !window.window_should_close() {
         // ========== ACTUALIZACIÓN DE ROTACIÓN GLOBAL ==========
         scene_rotation_angle += scene_rotation_speed;
         
-        // Optimización: solo crear objetos rotados si hay rotación
-        let objects = if scene_rotation_angle == 0.0 {
-            // Usar directamente los objetos base si no hay rotación
-            base_objects.to_vec()
+        let mut scene_changed = false;
+
+        // Detectar modificadores una sola vez
+        let shift_pressed = window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+        let ctrl_pressed = window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || window.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+        // En vuelo libre Ctrl baja la cámara: sus gestos de edición solo valen en órbita
+        let edit_modifier = ctrl_pressed && !free_fly;
+
+        // ========== EDICIÓN DE ESCENA ==========
+        // Sobre el objeto seleccionado: Ctrl + arrastrar lo mueve en el plano de la
+        // vista (con Shift, en profundidad), Ctrl + rueda lo escala, N / Shift + N
+        // cambian su material, Insert lo duplica y Supr lo borra. O añade una
+        // primitiva en el centro de la vista (Shift + O elige cuál) y Ctrl + Z /
+        // Ctrl + Y deshacen y rehacen. Se procesa antes de construir la vista del frame.
+        let mut edits_changed = false;
+        // Las ediciones viven en el espacio de la escena sin girar
+        let unrotate = Transform::rotation_y(-scene_rotation_angle);
+        if let Some(selected) = selection.as_mut() {
+            let index = selected.index;
+            let pivot = unrotate.apply_point(selected.hit.point);
+            // Un solo paso de deshacer por gesto, con el primer cambio: un clic sin
+            // arrastre no guarda nada y los pasos de la rueda se agrupan
+            if edit_modifier && window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+                let delta = window.get_mouse_delta();
+                if delta.x != 0.0 || delta.y != 0.0 {
+                    if !object_grabbed {
+                        editor.grab(index, pivot);
+                        object_grabbed = true;
+                    }
+                    // Escala del plano del objeto: el punto agarrado sigue al cursor
+                    let depth = (selected.hit.point - camera.eye).dot(camera.forward).max(0.1);
+                    let units_per_pixel = 2.0 * depth * (camera.fov * 0.5).tan() / window_height as f32;
+                    let offset = if shift_pressed {
+                        camera.forward * (-delta.y * units_per_pixel)
+                    } else {
+                        camera.right * (delta.x * units_per_pixel) - camera.up * (delta.y * units_per_pixel)
+                    };
+                    editor.translate(index, unrotate.apply_point(offset));
+                    selected.hit.point += offset;
+                    scene_changed = true;
+                }
+            }
+            let wheel = window.get_mouse_wheel_move();
+            if edit_modifier && wheel != 0.0 {
+                if !object_grabbed {
+                    editor.grab(index, pivot);
+                    object_grabbed = true;
+                }
+                editor.scale(index, 1.1f32.powf(wheel));
+                scene_changed = true;
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_N) {
+                editor.cycle_material(index, if shift_pressed { -1 } else { 1 });
+                edits_changed = true;
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_INSERT) {
+                // La copia queda seleccionada para poder colocarla
+                selected.index = editor.duplicate(index);
+                edits_changed = true;
+            }
+
+            // ========== EDITOR DE MATERIALES (Tab) ==========
+            // Deslizadores que cambian en vivo el material con nombre del objeto:
+            // se ajusta en su sitio y lo siguen todos los objetos que lo usan
+            if material_editor_open {
+                let mouse = window.get_mouse_position();
+                let panels = selection_panels(&window, selected, &editor, true, window_width - inspector_width, window_width);
+                let panel = &panels[1];
+                let named = editor.named_material(index, &selected.hit.material);
+                if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+                    && let Some(row) = panel.row_at(&window, mouse.x, mouse.y)
+                {
+                    material_drag = Some(row);
+                    match (PanelRow::at(row), named) {
+                        (Some(texture_row @ (PanelRow::Texture | PanelRow::NormalMap)), Some(named)) => {
+                            editor.checkpoint();
+                            edits_changed = true;
+                            let ids = texture_manager.texture_ids();
+                            let material = editor.named_material_mut(named);
+                            if texture_row == PanelRow::Texture {
+                                material.texture_id = material_editor::next_texture(&material.texture_id, &ids, false);
+                            } else {
+                                material.normal_map_id = material_editor::next_texture(&material.normal_map_id, &ids, true);
+                            }
+                        }
+                        (Some(PanelRow::Export), _) => {
+                            let message = match material_editor::export(materials_export_path, editor.tuned_materials()) {
+                                Ok(()) => format!("Materiales ajustados exportados a {}", materials_export_path.display()),
+                                Err(error) => error,
+                            };
+                            status_message = Some((message, window.get_time()));
+                        }
+                        _ => {}
+                    }
+                }
+                // Como al mover objetos: el paso de deshacer se guarda con el primer
+                // cambio real, no al pulsar sobre el deslizador
+                if window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
+                    && let Some(PanelRow::Slider(slider)) = material_drag.and_then(PanelRow::at)
+                    && let Some(named) = named
+                    && let Some(value) = material_editor::slider_value(&shown_material(selected, &editor), slider, panel.slider_fraction(&window, mouse.x))
+                {
+                    if !material_grabbed {
+                        editor.checkpoint();
+                        material_grabbed = true;
+                    }
+                    material_editor::set_slider(editor.named_material_mut(named), slider, value);
+                }
+                scene_changed |= material_drag.is_some();
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_TAB) {
+            material_editor_open = !material_editor_open;
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_DELETE)
+            && let Some(selected) = selection.take()
+        {
+            editor.delete(selected.index);
+            edits_changed = true;
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_O) {
+            if shift_pressed {
+                primitive_kind = primitive_kind.next();
+                status_message = Some((format!("Primitiva a añadir (O): {}", primitive_kind.name()), window.get_time()));
+            } else {
+                editor.add(primitive_kind, unrotate.apply_point(camera.center), &mut base_objects);
+                edits_changed = true;
+            }
+        }
+        if edit_modifier && window.is_key_pressed(KeyboardKey::KEY_Z) && editor.undo(&mut base_objects)
+            || edit_modifier && window.is_key_pressed(KeyboardKey::KEY_Y) && editor.redo(&mut base_objects)
+        {
+            // La selección puede apuntar a un objeto que ya no existe
+            selection = selection.filter(|selected| selected.index < editor.slots().len() && !editor.slots()[selected.index].deleted);
+            edits_changed = true;
+        }
+        // El gesto acaba al soltar el botón o Ctrl; entonces se guarda
+        if object_grabbed && (!edit_modifier || window.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT)) {
+            object_grabbed = false;
+            edits_changed = true;
+        }
+        if material_grabbed && !window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            material_grabbed = false;
+            edits_changed = true;
+        }
+        if edits_changed {
+            scene_changed = true;
+            if let Err(error) = editor.save(scene_edits_path) {
+                eprintln!("{}", error);
+            }
+        }
+
+        // ========== TIEMPO (T, [ y ]) ==========
+        if window.is_key_pressed(KeyboardKey::KEY_T) {
+            time_playing = !time_playing;
+        }
+        let frame_time = window.get_frame_time();
+        let mut time_step = if time_playing { frame_time } else { 0.0 };
+        if window.is_key_down(KeyboardKey::KEY_RIGHT_BRACKET) {
+            time_step += scrub_speed * frame_time;
+        }
+        if window.is_key_down(KeyboardKey::KEY_LEFT_BRACKET) {
+            time_step -= scrub_speed * frame_time;
+        }
+        // Solo hay que redibujar si algo de lo que se ve depende del tiempo
+        let time_dependent = animated_materials
+            || editor.tuned_materials().any(|tuned| tuned.material.is_animated())
+            || !animation.objects.is_empty()
+            || animation.light.is_some()
+            || (camera_animated && animation.camera.is_some());
+        if time_step != 0.0 {
+            scene_time = (scene_time + time_step).max(0.0);
+            scene_changed |= time_dependent;
+        }
+
+        // ========== ANIMACIÓN POR KEYFRAMES (C: cámara animada) ==========
+        if window.is_key_pressed(KeyboardKey::KEY_C) {
+            camera_animated = !camera_animated;
+            scene_changed = true;
+        }
+        if let Some(light_animation) = &animation.light {
+            light_animation.apply(&mut light, scene_time);
+        }
+        // La cámara solo se recoloca si el tiempo avanza, para no anular el refinado en pausa
+        if let Some(camera_animation) = animation.camera.as_ref().filter(|_| camera_animated && scene_changed) {
+            camera_animation.apply(&mut camera, scene_time);
+        }
+
+        // ========== MARCADORES DE CÁMARA (F1-F8, Shift + F1-F8, F9) ==========
+        let storing = window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+        for (slot, key) in bookmark_keys.iter().enumerate() {
+            if !window.is_key_pressed(*key) {
+                continue;
+            }
+            if storing {
+                // Se reescribe la ranura conservando su nombre; F5 siempre es la ranura 5
+                let name = match bookmarks.get(slot) {
+                    Some(Some(bookmark)) => bookmark.name.clone(),
+                    _ => format!("vista_{}", slot + 1),
+                };
+                if slot >= bookmarks.len() {
+                    bookmarks.resize(slot + 1, None);
+                }
+                bookmarks[slot] = Some(Bookmark::capture(&name, &camera));
+                if let Err(error) = bookmarks::save(bookmarks_path, &bookmarks) {
+                    eprintln!("{}", error);
+                }
+            } else if let Some(Some(bookmark)) = bookmarks.get(slot) {
+                camera.set_aperture_shape(bookmark.aperture_shape);
+                flythrough = Some(Flythrough::to(&camera, bookmark, bookmark_glide));
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_F9) {
+            flythrough = match flythrough {
+                Some(_) => None,
+                None => Flythrough::tour(&bookmarks.iter().flatten().cloned().collect::<Vec<_>>(), tour_travel, tour_hold),
+            };
+        }
+        if let Some(flight) = &mut flythrough
+            && !flight.update(&mut camera, frame_time)
+        {
+            flythrough = None;
+        }
+
+        // ========== RECORRIDO DE CÁMARA (K, Shift + K, coma/punto, Retroceso, H, J) ==========
+        let mut path_edited = false;
+        if window.is_key_pressed(KeyboardKey::KEY_K) {
+            match path_selected.filter(|_| storing) {
+                Some(index) => camera_path.set_view(index, &camera),
+                None => {
+                    camera_path.push_view(&camera);
+                    path_selected = Some(camera_path.len() - 1);
+                }
+            }
+            path_edited = true;
+        }
+        if !camera_path.is_empty() {
+            let last = camera_path.len() - 1;
+            let selection = if window.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+                Some(path_selected.map_or(0, |index| (index + 1).min(last)))
+            } else if window.is_key_pressed(KeyboardKey::KEY_COMMA) {
+                Some(path_selected.map_or(last, |index| index.saturating_sub(1)))
+            } else {
+                None
+            };
+            if let Some(index) = selection {
+                path_selected = Some(index);
+                path_playing = false;
+                camera_path.show_view(index, &mut camera);
+            }
+        }
+        if let Some(index) = path_selected.filter(|_| window.is_key_pressed(KeyboardKey::KEY_BACKSPACE)) {
+            camera_path.remove_view(index);
+            // Queda seleccionado el punto anterior (o el nuevo primero)
+            path_selected = index.checked_sub(1).or((!camera_path.is_empty()).then_some(0));
+            path_edited = true;
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_H) {
+            camera_path.set_kind(match camera_path.eye.kind() {
+                SplineKind::CatmullRom => SplineKind::Bezier,
+                SplineKind::Bezier => SplineKind::CatmullRom,
+            });
+            path_edited = true;
+        }
+        if path_edited
+            && let Err(error) = camera_path.save(camera_path_file)
+        {
+            eprintln!("{}", error);
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_J) {
+            path_playing = !path_playing && !camera_path.is_empty();
+            path_time = 0.0;
+        }
+        if path_playing {
+            path_time += frame_time;
+            camera_path.apply(&mut camera, path_time);
+        }
+
+        // ========== NIEBLA (F) ==========
+        if window.is_key_pressed(KeyboardKey::KEY_F) {
+            fog_mode = (fog_mode + 1) % 3;
+            atmosphere = base_atmosphere(&media);
+            match fog_mode {
+                1 => atmosphere = atmosphere.with_fog(Fog::homogeneous(0.04, Vector3::new(0.8, 0.82, 0.85))),
+                2 => atmosphere = atmosphere.with_fog(Fog::height(0.35, -3.0, 0.9, Vector3::new(0.85, 0.85, 0.9))),
+                _ => {}
+            }
+            scene_changed = true;
+        }
+        
+        if window.is_key_pressed(KeyboardKey::KEY_M) {
+            motion_blur = !motion_blur;
+            scene_changed = true;
+        }
+        // El obturador avanza solo cuando el tiempo o la rotación cambian: con la escena
+        // quieta conserva el último intervalo y el refinado progresivo lo promedia
+        if !motion_blur {
+            previous_time = scene_time;
+            previous_rotation = scene_rotation_angle;
+        } else if scene_time != closed_time || scene_rotation_angle != closed_rotation {
+            previous_time = closed_time;
+            previous_rotation = closed_rotation;
+        }
+        closed_time = scene_time;
+        closed_rotation = scene_rotation_angle;
+        let shutter = if motion_blur {
+            Shutter {
+                open_time: previous_time,
+                close_time: scene_time,
+                open_rotation: previous_rotation,
+                close_rotation: scene_rotation_angle,
+            }
         } else {
-            // Crear objetos rotados solo cuando es necesario
-            create_rotated_objects(&base_objects, scene_rotation_angle)
+            Shutter::instant(scene_time, scene_rotation_angle)
         };
+
+        // Vista del frame: los objetos base con sus ediciones, animaciones y la rotación
+        let frame_objects = create_frame_objects(&base_objects, &editor, shutter, &animation);
+        let objects: &[Box<dyn RayIntersect + '_>] = &frame_objects;
         
+        // ========== PROYECCIÓN (P) Y CAMPO DE VISIÓN (- / =) ==========
+        if window.is_key_pressed(KeyboardKey::KEY_P) {
+            camera.set_projection(match camera.projection {
+                Projection::Perspective => Projection::Orthographic,
+                Projection::Orthographic => Projection::Fisheye { fov: PI },
+                Projection::Fisheye { .. } => Projection::Equirectangular,
+                Projection::Equirectangular => Projection::Perspective,
+            });
+        }
+        if window.is_key_down(KeyboardKey::KEY_EQUAL) {
+            camera.set_fov(camera.fov + fov_step);
+        }
+        if window.is_key_down(KeyboardKey::KEY_MINUS) {
+            camera.set_fov(camera.fov - fov_step);
+        }
+
+        // ========== PROFUNDIDAD DE CAMPO (Z/X, B) ==========
+        if window.is_key_down(KeyboardKey::KEY_X) {
+            camera.set_aperture(camera.aperture + aperture_step);
+        }
+        if window.is_key_down(KeyboardKey::KEY_Z) && !ctrl_pressed && camera.aperture > 0.0 {
+            camera.set_aperture(camera.aperture - aperture_step);
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_B) {
+            camera.set_aperture_shape(match camera.aperture_shape {
+                ApertureShape::Circular => ApertureShape::Polygon { blades: 6, rotation: 0.0 },
+                ApertureShape::Polygon { .. } => ApertureShape::Circular,
+            });
+        }
+
+        // ========== RATÓN: CLIC Y DOBLE CLIC ==========
+        // Un clic sin arrastrar enfoca la superficie bajo el cursor; con doble
+        // clic además pasa a ser el centro de la órbita
+        if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            left_drag_distance = 0.0;
+        }
+        if window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            left_drag_distance += window.get_mouse_delta().length();
+        }
+        if window.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) && left_drag_distance < click_tolerance && material_drag.is_none() {
+            let mouse = window.get_mouse_position();
+            let now = window.get_time();
+            if let Some(point) = pick(&camera, objects, mouse.x, mouse.y, window_width as f32, window_height as f32).map(|pick| pick.hit.point) {
+                // El plano focal es perpendicular al eje: se mide la distancia a lo largo de él.
+                // Con la lente estenopeica todo está enfocado y no hay nada que reenfocar
+                if camera.aperture > 0.0 {
+                    camera.set_focal_distance((point - camera.eye).dot(camera.forward));
+                }
+                if now - last_click_time < double_click_time {
+                    camera.recenter(point);
+                }
+            }
+            last_click_time = now;
+        }
+
+        // ========== SELECCIÓN (clic derecho) ==========
+        // Un clic derecho sin arrastrar selecciona el objeto bajo el cursor; sobre
+        // el cielo deselecciona. El objeto se tiñe y el inspector muestra su impacto.
+        if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+            right_drag_distance = 0.0;
+        }
+        if window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_RIGHT) {
+            right_drag_distance += window.get_mouse_delta().length();
+        }
+        if window.is_mouse_button_released(MouseButton::MOUSE_BUTTON_RIGHT) && right_drag_distance < click_tolerance {
+            let mouse = window.get_mouse_position();
+            // El material del objeto se guarda sin ajustes: así se reconoce su material con nombre
+            selection = pick(&camera, objects, mouse.x, mouse.y, window_width as f32, window_height as f32).map(|mut pick| {
+                editor.untune(&mut pick.hit.material);
+                pick
+            });
+            scene_changed = true;
+        }
+
         let camera_was_changed = camera.is_changed();
         
         // ========== CONTROLES OPTIMIZADOS ==========
         
-        // Detectar modificadores una sola vez
-        let shift_pressed = window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
         let zoom_multiplier = if shift_pressed { 3.0 } else { 1.0 };
         
         // ========== CONTROLES DE SKYBOX ==========
@@ -870,29 +2036,125 @@ fn main() {
         
         // ========== CONTROLES DE CÁMARA OPTIMIZADOS ==========
         
-        // Órbita de cámara (flechas)
-        if window.is_key_down(KeyboardKey::KEY_LEFT) {
-            camera.orbit(rotation_speed, 0.0);
-        }
-        if window.is_key_down(KeyboardKey::KEY_RIGHT) {
-            camera.orbit(-rotation_speed, 0.0);
-        }
-        if window.is_key_down(KeyboardKey::KEY_UP) {
-            camera.orbit(0.0, -rotation_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_DOWN) {
-            camera.orbit(0.0, rotation_speed);
+        // Modo de cámara (V): órbita alrededor del centro o vuelo libre
+        if window.is_key_pressed(KeyboardKey::KEY_V) {
+            free_fly = !free_fly;
         }
+
+        if free_fly {
+            // ========== VUELO LIBRE ==========
+            // Shift acelera y Alt frena sobre la velocidad base (Page Up/Down)
+            let alt_pressed = window.is_key_down(KeyboardKey::KEY_LEFT_ALT) || window.is_key_down(KeyboardKey::KEY_RIGHT_ALT);
+            let fly_speed = zoom_speed * if shift_pressed { 3.0 } else if alt_pressed { 0.25 } else { 1.0 };
+            let axis = |positive: KeyboardKey, negative: KeyboardKey| {
+                (window.is_key_down(positive) as i32 - window.is_key_down(negative) as i32) as f32
+            };
+
+            // Traslación (WASD, Espacio sube, Ctrl baja)
+            let forward = axis(KeyboardKey::KEY_W, KeyboardKey::KEY_S);
+            let right = axis(KeyboardKey::KEY_D, KeyboardKey::KEY_A);
+            let up = axis(KeyboardKey::KEY_SPACE, KeyboardKey::KEY_LEFT_CONTROL);
+            if forward != 0.0 || right != 0.0 || up != 0.0 {
+                camera.fly(right * fly_speed, up * fly_speed, forward * fly_speed);
+            }
+
+            // Mirar con las flechas o arrastrando con el botón derecho
+            let mut yaw = axis(KeyboardKey::KEY_LEFT, KeyboardKey::KEY_RIGHT) * rotation_speed;
+            let mut pitch = axis(KeyboardKey::KEY_UP, KeyboardKey::KEY_DOWN) * rotation_speed;
+            if window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_RIGHT) {
+                let delta = window.get_mouse_delta();
+                yaw -= delta.x * mouse_sensitivity;
+                pitch -= delta.y * mouse_sensitivity;
+            }
+            if yaw != 0.0 || pitch != 0.0 {
+                camera.look(yaw, pitch);
+            }
+
+            // Alabeo (Q/E)
+            let roll = axis(KeyboardKey::KEY_E, KeyboardKey::KEY_Q);
+            if roll != 0.0 {
+                camera.roll(roll * rotation_speed);
+            }
+        } else {
+            // Órbita de cámara (flechas)
+            if window.is_key_down(KeyboardKey::KEY_LEFT) {
+                camera.orbit(rotation_speed, 0.0);
+            }
+            if window.is_key_down(KeyboardKey::KEY_RIGHT) {
+                camera.orbit(-rotation_speed, 0.0);
+            }
+            if window.is_key_down(KeyboardKey::KEY_UP) {
+                camera.orbit(0.0, -rotation_speed);
+            }
+            if window.is_key_down(KeyboardKey::KEY_DOWN) {
+                camera.orbit(0.0, rotation_speed);
+            }
         
-        // Zoom optimizado (W/S con modificador Shift)
-        let effective_zoom_speed = zoom_speed * zoom_multiplier;
-        if window.is_key_down(KeyboardKey::KEY_W) {
-            camera.zoom_in(effective_zoom_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_S) {
-            camera.zoom_out(effective_zoom_speed);
-        }
+            // Ratón: arrastrar con el botón izquierdo orbita; con el central o
+            // con Shift + izquierdo desplaza el centro (con Ctrl edita el objeto)
+            let middle_down = window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_MIDDLE);
+            if middle_down || (window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) && !ctrl_pressed && material_drag.is_none()) {
+                let delta = window.get_mouse_delta();
+                if delta.x != 0.0 || delta.y != 0.0 {
+                    if middle_down || shift_pressed {
+                        // Escala del plano del centro: el punto agarrado sigue al cursor
+                        let units_per_pixel = 2.0 * camera.get_distance() * (camera.fov * 0.5).tan() / window_height as f32;
+                        camera.pan(-delta.x * units_per_pixel, delta.y * units_per_pixel);
+                    } else {
+                        camera.orbit(delta.x * mouse_sensitivity, delta.y * mouse_sensitivity);
+                    }
+                }
+            }
+
+            // Rueda: cada paso da un impulso de zoom que se amortigua en los frames siguientes
+            if !ctrl_pressed {
+                zoom_velocity += window.get_mouse_wheel_move() * camera.get_distance() * wheel_zoom_step;
+            }
+            if zoom_velocity.abs() > 0.001 {
+                if zoom_velocity > 0.0 {
+                    camera.zoom_in(zoom_velocity);
+                } else {
+                    camera.zoom_out(-zoom_velocity);
+                }
+                zoom_velocity *= zoom_damping;
+            } else {
+                zoom_velocity = 0.0;
+            }
+
+            // Zoom optimizado (W/S con modificador Shift)
+            let effective_zoom_speed = zoom_speed * zoom_multiplier;
+            if window.is_key_down(KeyboardKey::KEY_W) {
+                camera.zoom_in(effective_zoom_speed);
+            }
+            if window.is_key_down(KeyboardKey::KEY_S) {
+                camera.zoom_out(effective_zoom_speed);
+            }
+        
+            // ========== CONTROLES DE ROTACIÓN GLOBAL OPTIMIZADOS ==========
+        
+            // Rotación automática (Q/E)
+            if window.is_key_down(KeyboardKey::KEY_Q) {
+                scene_rotation_speed = (scene_rotation_speed + rotation_speed_increment).min(max_rotation_speed);
+            }
+            if window.is_key_down(KeyboardKey::KEY_E) {
+                scene_rotation_speed = (scene_rotation_speed - rotation_speed_increment).max(-max_rotation_speed);
+            }
         
+            // Rotación manual (A/D)
+            let manual_rotation_speed = rotation_speed_increment * if shift_pressed { 10.0 } else { 5.0 };
+            if window.is_key_down(KeyboardKey::KEY_A) {
+                scene_rotation_angle -= manual_rotation_speed;
+            }
+            if window.is_key_down(KeyboardKey::KEY_D) {
+                scene_rotation_angle += manual_rotation_speed;
+            }
+        
+            // Controles especiales (una sola verificación cada uno)
+            if window.is_key_pressed(KeyboardKey::KEY_SPACE) {
+                scene_rotation_speed = 0.0;
+            }
+        }
+
         // Control de velocidad de zoom
         if window.is_key_pressed(KeyboardKey::KEY_PAGE_UP) && zoom_speed < max_zoom_speed {
             zoom_speed = (zoom_speed + zoom_speed_increment).min(max_zoom_speed);
@@ -901,36 +2163,46 @@ fn main() {
             zoom_speed = (zoom_speed - zoom_speed_increment).max(min_zoom_speed);
         }
         
-        // ========== CONTROLES DE ROTACIÓN GLOBAL OPTIMIZADOS ==========
-        
-        // Rotación automática (Q/E)
-        if window.is_key_down(KeyboardKey::KEY_Q) {
-            scene_rotation_speed = (scene_rotation_speed + rotation_speed_increment).min(max_rotation_speed);
-        }
-        if window.is_key_down(KeyboardKey::KEY_E) {
-            scene_rotation_speed = (scene_rotation_speed - rotation_speed_increment).max(-max_rotation_speed);
-        }
-        
-        // Rotación manual (A/D)
-        let manual_rotation_speed = rotation_speed_increment * if shift_pressed { 10.0 } else { 5.0 };
-        if window.is_key_down(KeyboardKey::KEY_A) {
-            scene_rotation_angle -= manual_rotation_speed;
-        }
-        if window.is_key_down(KeyboardKey::KEY_D) {
-            scene_rotation_angle += manual_rotation_speed;
-        }
-        
-        // Controles especiales (una sola verificación cada uno)
-        if window.is_key_pressed(KeyboardKey::KEY_SPACE) {
-            scene_rotation_speed = 0.0;
-        }
         if window.is_key_pressed(KeyboardKey::KEY_R) {
             scene_rotation_angle = 0.0;
             scene_rotation_speed = 0.0;
         }
 
+        // ========== COLISIONES DE CÁMARA (L) ==========
+        // Tras todos los movimientos del frame: órbita, zoom, vuelo, recorridos...
+        if window.is_key_pressed(KeyboardKey::KEY_L) {
+            camera_collisions = !camera_collisions;
+        }
+        if camera_collisions {
+            avoid_collisions(&mut camera, closed_camera.eye, objects, camera_clearance, free_fly);
+        }
+        // Igual que el tiempo: la cámara al abrir el obturador solo avanza si se movió
+        if !motion_blur {
+            previous_camera = camera.clone();
+        } else if camera.eye != closed_camera.eye || camera.center != closed_camera.center {
+            previous_camera = closed_camera.clone();
+        }
+        closed_camera = camera.clone();
+
+        let scene = Scene {
+            objects,
+            light: &light,
+            texture_manager: &texture_manager,
+            skybox: &skybox,
+            atmosphere: &atmosphere,
+            time: shutter.open_time,
+            shutter: shutter.close_time - shutter.open_time,
+            camera_open: (motion_blur && (previous_camera.eye != camera.eye || previous_camera.center != camera.center)).then_some(&previous_camera),
+            selected: selection.as_ref().map(|selection| selection.index),
+            rays: &ray_counters,
+        };
+
+        // Con desenfoque (obturador abierto o lente) el refinado promedia varias pasadas
+        let blurred = shutter.is_open() || scene.camera_open.is_some() || camera.aperture > 0.0;
+        let progressive_passes = if blurred { ACCUMULATION_PASSES } else { 1 };
+
         // Lógica híbrida mejorada con LOD adaptativo
-        if camera_was_changed {
+        if camera_was_changed || scene_changed {
             frames_since_camera_change = 0;
             use_progressive = false;
             current_lod = 4; // Empezar con baja calidad
@@ -946,13 +2218,13 @@ fn main() {
         frames_since_camera_change += 1;
         
         // Renderizado adaptativo basado en frames y LOD
-        if frames_since_camera_change <= 8 {
+        if frames_since_camera_change <= ADAPTIVE_FRAMES {
             // Fase inicial: renderizado adaptativo con mejora gradual
-            render_adaptive(&mut framebuffer, &objects, &camera, &light, &texture_manager, &skybox, current_lod);
-        } else if frames_since_camera_change <= 20 {
+            render_adaptive(&mut framebuffer, &scene, &camera, current_lod);
+        } else if frames_since_camera_change <= FULL_RENDER_FRAMES {
             // Fase intermedia: renderizado completo si no está hecho
             if !render_complete {
-                render(&mut framebuffer, &objects, &camera, &light, &texture_manager, &skybox);
+                render(&mut framebuffer, &scene, &camera);
                 render_complete = true;
             }
         } else {
@@ -966,17 +2238,44 @@ fn main() {
             if !render_complete {
                 render_complete = render_progressive(
                     &mut framebuffer, 
-                    &objects, 
+                    &scene, 
                     &camera, 
-                    &light, 
-                    &texture_manager, 
-                    &skybox,
                     samples_per_frame,
-                    &mut current_sample
+                    &mut current_sample,
+                    &mut accumulation,
+                    progressive_passes,
                 );
             }
         }
+
+        // ========== HUD DE ESTADÍSTICAS (I) ==========
+        if window.is_key_pressed(KeyboardKey::KEY_I) {
+            hud.visible = !hud.visible;
+        }
+        hud.record_frame(&ray_counters, window.get_time());
+        let render_stage = if frames_since_camera_change <= ADAPTIVE_FRAMES {
+            RenderStage::Adaptive
+        } else if frames_since_camera_change <= FULL_RENDER_FRAMES {
+            RenderStage::Full
+        } else {
+            RenderStage::Progressive { sample: current_sample, total: framebuffer.width * framebuffer.height * progressive_passes }
+        };
         
+        // HUD arriba a la izquierda; inspector y editor de materiales, arriba a la derecha
+        framebuffer.set_overlay(
+            hud.visible
+                .then(|| hud.panel(current_lod, &render_stage))
+                .into_iter()
+                .chain(selection.iter().flat_map(|selection| {
+                    selection_panels(&window, selection, &editor, material_editor_open, window_width - inspector_width, window_width)
+                }))
+                .chain(message_panel(status_message.as_ref(), window.get_time(), window_height))
+                .collect(),
+        );
+        if !window.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            material_drag = None;
+        }
+
         // Usar el sistema optimizado de blit y caché
         framebuffer.swap_buffers(&mut window, &thread);
     }
@@ -1,4 +1,7 @@
 use raylib::prelude::{Color, Vector3};
+use std::sync::Arc;
+use crate::procedural_texture::ProceduralTexture;
+use crate::shader_graph::{MaterialGraph, MaterialInput, ShaderNode};
 
 #[derive(Clone)]
 pub struct Material {
@@ -8,6 +11,8 @@ pub struct Material {
     pub refractive_index: f32,
     pub texture_id: Option<String>,
     pub normal_map_id: Option<String>,
+    pub uv_flow: (f32, f32), // Desplazamiento de las UV por segundo (agua que corre, lava que fluye)
+    pub graph: Option<Arc<MaterialGraph>>, // Compartido: clonar el material no copia los nodos
 }
 
 impl Material {
@@ -26,9 +31,41 @@ impl Material {
             refractive_index,
             texture_id,
             normal_map_id,
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
+    pub fn with_uv_flow(mut self, u_speed: f32, v_speed: f32) -> Self {
+        self.uv_flow = (u_speed, v_speed);
+        self
+    }
+
+    /// Feeds one of the material inputs from a shader node
+    pub fn with_node(mut self, input: MaterialInput, node: ShaderNode) -> Self {
+        let graph = self.graph.get_or_insert_with(Default::default);
+        Arc::make_mut(graph).connect(input, node);
+        self
+    }
+
+    /// Whether the material looks different as scene time passes: flowing
+    /// UVs or graph nodes that depend on time
+    pub fn is_animated(&self) -> bool {
+        self.uv_flow != (0.0, 0.0) || self.graph.as_ref().is_some_and(|graph| graph.is_animated())
+    }
+
+    pub fn with_procedural_color(self, texture: ProceduralTexture) -> Self {
+        self.with_node(MaterialInput::Color, ShaderNode::noise_color(texture))
+    }
+
+    pub fn with_bump(self, texture: ProceduralTexture, strength: f32) -> Self {
+        self.with_node(MaterialInput::Normal, ShaderNode::bump(ShaderNode::noise(texture), strength))
+    }
+
+    pub fn with_roughness(self, texture: ProceduralTexture) -> Self {
+        self.with_node(MaterialInput::Roughness, ShaderNode::noise(texture))
+    }
+
     pub fn black() -> Self {
         Material {
             diffuse: Vector3::zero(),
@@ -37,6 +74,8 @@ impl Material {
             refractive_index: 0.0,
             texture_id: None,
             normal_map_id: None,
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -49,6 +88,8 @@ impl Material {
             refractive_index: 1.0,
             texture_id: Some("assets/grass_dirt.png".to_string()),
             normal_map_id: Some("assets/grass_dirt_normal.png".to_string()),
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -61,6 +102,8 @@ impl Material {
             refractive_index: 1.0,
             texture_id: Some("assets/castle_stone.png".to_string()),
             normal_map_id: Some("assets/castle_stone_normal.png".to_string()),
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -73,6 +116,8 @@ impl Material {
             refractive_index: 1.33, // Índice de refracción del agua
             texture_id: Some("assets/water_waves.png".to_string()),
             normal_map_id: Some("assets/water_normal.png".to_string()),
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -85,6 +130,8 @@ impl Material {
             refractive_index: 1.0,
             texture_id: Some("assets/lava_bubbles.png".to_string()),
             normal_map_id: Some("assets/lava_normal.png".to_string()),
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -97,6 +144,8 @@ impl Material {
             refractive_index: 1.5, // Índice de refracción del vidrio/cristal
             texture_id: None, // No necesita textura compleja, solo color base
             normal_map_id: None,
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -128,6 +177,8 @@ impl Material {
             refractive_index: 1.0,
             texture_id: None, // Usar color base por ahora
             normal_map_id: None,
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -140,6 +191,8 @@ impl Material {
             refractive_index: 1.0,
             texture_id: None, // Color base natural
             normal_map_id: None,
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
 
@@ -152,8 +205,27 @@ impl Material {
             refractive_index: 1.0,
             texture_id: Some("assets/castle_stone.png".to_string()), // Usar textura de castillo
             normal_map_id: Some("assets/castle_stone_normal.png".to_string()),
+            uv_flow: (0.0, 0.0),
+            graph: None,
         }
     }
+
+    /// Named materials offered by the scene editor, in cycling order
+    pub fn presets() -> Vec<(&'static str, Material)> {
+        vec![
+            ("tierra_hierba", Material::tierra_hierba()),
+            ("piedra_castillo", Material::piedra_castillo()),
+            ("piedra_oscura", Material::piedra_oscura()),
+            ("madera", Material::madera()),
+            ("hojas", Material::hojas()),
+            ("agua", Material::agua()),
+            ("lava", Material::lava()),
+            ("cristal_gema", Material::cristal_gema()),
+            ("cristal_esmeralda", Material::cristal_esmeralda()),
+            ("cristal_rubi", Material::cristal_rubi()),
+            ("cristal_zafiro", Material::cristal_zafiro()),
+        ]
+    }
 }
 
 pub fn vector3_to_color(v: Vector3) -> Color {
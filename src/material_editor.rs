@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::Path;
+use crate::framebuffer::OverlayPanel;
+use crate::material::Material;
+use crate::scene_edit::NamedMaterial;
+use crate::shader_graph::MaterialInput;
+
+/// Material property tuned with one slider of the panel
+struct Slider {
+    label: &'static str,
+    min: f32,
+    max: f32,
+    get: fn(&Material) -> f32,
+    set: fn(&mut Material, f32),
+    input: Option<MaterialInput>, // Entrada del grafo que sustituye a la propiedad al sombrear
+}
+
+impl Slider {
+    // Con la entrada conectada a un nodo, mover el deslizador no cambiaría la imagen
+    fn driven_by_graph(&self, material: &Material) -> bool {
+        self.input.is_some_and(|input| material.graph.as_ref().is_some_and(|graph| graph.input(input).is_some()))
+    }
+}
+
+const SLIDERS: [Slider; 9] = [
+    Slider { label: "Difuso R", min: 0.0, max: 1.0, get: |m| m.diffuse.x, set: |m, v| m.diffuse.x = v, input: Some(MaterialInput::Color) },
+    Slider { label: "Difuso G", min: 0.0, max: 1.0, get: |m| m.diffuse.y, set: |m, v| m.diffuse.y = v, input: Some(MaterialInput::Color) },
+    Slider { label: "Difuso B", min: 0.0, max: 1.0, get: |m| m.diffuse.z, set: |m, v| m.diffuse.z = v, input: Some(MaterialInput::Color) },
+    Slider { label: "Especular", min: 0.0, max: 200.0, get: |m| m.specular, set: |m, v| m.specular = v, input: Some(MaterialInput::Specular) },
+    Slider { label: "Albedo difuso", min: 0.0, max: 1.0, get: |m| m.albedo[0], set: |m, v| m.albedo[0] = v, input: None },
+    Slider { label: "Albedo especular", min: 0.0, max: 1.0, get: |m| m.albedo[1], set: |m, v| m.albedo[1] = v, input: None },
+    Slider { label: "Reflexión", min: 0.0, max: 1.0, get: |m| m.albedo[2], set: |m, v| m.albedo[2] = v, input: Some(MaterialInput::Reflectivity) },
+    Slider { label: "Transparencia", min: 0.0, max: 1.0, get: |m| m.albedo[3], set: |m, v| m.albedo[3] = v, input: Some(MaterialInput::Transparency) },
+    Slider { label: "Índice refr.", min: 1.0, max: 2.5, get: |m| m.refractive_index, set: |m, v| m.refractive_index = v, input: None },
+];
+
+/// What a click on a row of the panel does
+#[derive(Clone, Copy, PartialEq)]
+pub enum PanelRow {
+    Slider(usize),
+    Texture,
+    NormalMap,
+    Export,
+}
+
+impl PanelRow {
+    // Filas: título, deslizadores, textura, normal map y exportar
+    pub fn at(row: usize) -> Option<Self> {
+        match row.checked_sub(1)? {
+            index if index < SLIDERS.len() => Some(PanelRow::Slider(index)),
+            index if index == SLIDERS.len() => Some(PanelRow::Texture),
+            index if index == SLIDERS.len() + 1 => Some(PanelRow::NormalMap),
+            index if index == SLIDERS.len() + 2 => Some(PanelRow::Export),
+            _ => None,
+        }
+    }
+}
+
+/// Panel listing the properties of `material`, one slider per number; rows
+/// whose input comes from the material graph are marked and have no slider
+pub fn panel(x: i32, y: i32, name: &str, material: &Material) -> OverlayPanel {
+    // Solo el nombre del archivo: la ruta completa ensancharía el panel
+    let optional = |id: &Option<String>| {
+        id.as_deref().map_or("-".to_string(), |id| Path::new(id).file_name().unwrap_or_default().to_string_lossy().into_owned())
+    };
+    let mut lines = vec![format!("Material: {}", name)];
+    lines.extend(SLIDERS.iter().map(|slider| {
+        if slider.driven_by_graph(material) {
+            format!("{}  (grafo)", slider.label)
+        } else {
+            format!("{}  {:.2}", slider.label, (slider.get)(material))
+        }
+    }));
+    lines.push(format!("Textura: {}", optional(&material.texture_id)));
+    lines.push(format!("Normal map: {}", optional(&material.normal_map_id)));
+    lines.push("[ Exportar ]".to_string());
+
+    SLIDERS
+        .iter()
+        .enumerate()
+        .filter(|(_, slider)| !slider.driven_by_graph(material))
+        .fold(OverlayPanel::new(x, y, lines), |panel, (index, slider)| {
+            panel.with_slider(index + 1, ((slider.get)(material) - slider.min) / (slider.max - slider.min))
+        })
+}
+
+/// Value of slider `index` at `fraction` of its range, or `None` when it
+/// would not change `material`: the same value, or an input driven by the graph
+pub fn slider_value(material: &Material, index: usize, fraction: f32) -> Option<f32> {
+    let slider = &SLIDERS[index];
+    let value = slider.min + (slider.max - slider.min) * fraction.clamp(0.0, 1.0);
+    (!slider.driven_by_graph(material) && value != (slider.get)(material)).then_some(value)
+}
+
+/// Sets the property of slider `index` to `value`
+pub fn set_slider(material: &mut Material, index: usize, value: f32) {
+    (SLIDERS[index].set)(material, value);
+}
+
+/// Texture after `current` among the loaded ones (normal maps or color
+/// textures), passing through none after the last
+pub fn next_texture(current: &Option<String>, ids: &[String], normal_map: bool) -> Option<String> {
+    let candidates: Vec<&String> = ids.iter().filter(|id| id.contains("_normal") == normal_map).collect();
+    let next = match current.as_ref().and_then(|current| candidates.iter().position(|id| *id == current)) {
+        Some(position) => position + 1,
+        None => 0,
+    };
+    candidates.get(next).map(|id| id.to_string())
+}
+
+/// Writes the tuned materials as constructors under their own names, ready
+/// to replace the originals in the `impl Material` block of `material.rs`
+pub fn export<'a>(path: &Path, materials: impl Iterator<Item = &'a NamedMaterial>) -> Result<(), String> {
+    let optional = |id: &Option<String>| match id {
+        Some(id) => format!("Some({:?}.to_string())", id),
+        None => "None".to_string(),
+    };
+    let mut text = String::from("// Materiales ajustados en el editor: sustituyen a los constructores del mismo nombre en `impl Material`\n");
+    for NamedMaterial { name, material: m, .. } in materials {
+        text.push_str(&format!(
+            "
+    // Ajustado en el editor de materiales
+    pub fn {name}() -> Self {{
+        Material {{
+            diffuse: Vector3::new({:?}, {:?}, {:?}),
+            albedo: [{:?}, {:?}, {:?}, {:?}],
+            specular: {:?},
+            refractive_index: {:?},
+            texture_id: {},
+            normal_map_id: {},
+            uv_flow: ({:?}, {:?}),
+            graph: None,{}
+        }}
+    }}
+",
+            m.diffuse.x, m.diffuse.y, m.diffuse.z,
+            m.albedo[0], m.albedo[1], m.albedo[2], m.albedo[3],
+            m.specular,
+            m.refractive_index,
+            optional(&m.texture_id),
+            optional(&m.normal_map_id),
+            m.uv_flow.0, m.uv_flow.1,
+            if m.graph.is_some() { " // El grafo de nodos del original no se exporta" } else { "" },
+        ));
+    }
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,170 @@
+use raylib::prelude::Vector3;
+
+// Hash entero de una celda de la red (sin tablas de permutación)
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^ (h >> 15)
+}
+
+// Producto escalar con uno de los 12 gradientes de Perlin (aristas de un cubo)
+fn gradient(hash: u32, x: f32, y: f32, z: f32) -> f32 {
+    match hash % 12 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+// Curva de interpolación de quinto grado (derivadas continuas)
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Improved Perlin gradient noise in roughly [-1, 1]
+pub fn perlin(p: Vector3, seed: u32) -> f32 {
+    let (x0, y0, z0) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (fx, fy, fz) = (p.x - x0, p.y - y0, p.z - z0);
+    let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let h = hash(ix + dx, iy + dy, iz + dz, seed);
+        gradient(h, fx - dx as f32, fy - dy as f32, fz - dz as f32)
+    };
+
+    let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+    lerp(
+        lerp(lerp(corner(0, 0, 0), corner(1, 0, 0), u), lerp(corner(0, 1, 0), corner(1, 1, 0), u), v),
+        lerp(lerp(corner(0, 0, 1), corner(1, 0, 1), u), lerp(corner(0, 1, 1), corner(1, 1, 1), u), v),
+        w,
+    )
+}
+
+// Factores de sesgo del ruido simplex 3D (rejilla de tetraedros)
+const SIMPLEX_SKEW: f32 = 1.0 / 3.0;
+const SIMPLEX_UNSKEW: f32 = 1.0 / 6.0;
+
+/// Simplex noise in roughly [-1, 1]: cheaper than Perlin in 3D and without
+/// its axis-aligned artifacts
+pub fn simplex(p: Vector3, seed: u32) -> f32 {
+    // Celda del espacio sesgado y vértice de origen del simplex
+    let skew = (p.x + p.y + p.z) * SIMPLEX_SKEW;
+    let (i, j, k) = ((p.x + skew).floor(), (p.y + skew).floor(), (p.z + skew).floor());
+    let unskew = (i + j + k) * SIMPLEX_UNSKEW;
+    let d0 = Vector3::new(p.x - (i - unskew), p.y - (j - unskew), p.z - (k - unskew));
+
+    // Orden de los ejes para saber qué tetraedro contiene el punto
+    let (o1, o2) = if d0.x >= d0.y {
+        if d0.y >= d0.z {
+            ((1, 0, 0), (1, 1, 0))
+        } else if d0.x >= d0.z {
+            ((1, 0, 0), (1, 0, 1))
+        } else {
+            ((0, 0, 1), (1, 0, 1))
+        }
+    } else if d0.y < d0.z {
+        ((0, 0, 1), (0, 1, 1))
+    } else if d0.x < d0.z {
+        ((0, 1, 0), (0, 1, 1))
+    } else {
+        ((0, 1, 0), (1, 1, 0))
+    };
+
+    let (ii, jj, kk) = (i as i32, j as i32, k as i32);
+    let corners = [(0, 0, 0), o1, o2, (1, 1, 1)];
+    let mut sum = 0.0;
+    for (index, (ci, cj, ck)) in corners.into_iter().enumerate() {
+        let offset = index as f32 * SIMPLEX_UNSKEW;
+        let d = Vector3::new(d0.x - ci as f32 + offset, d0.y - cj as f32 + offset, d0.z - ck as f32 + offset);
+        let falloff = 0.6 - d.dot(d);
+        if falloff > 0.0 {
+            let h = hash(ii + ci, jj + cj, kk + ck, seed);
+            sum += falloff.powi(4) * gradient(h, d.x, d.y, d.z);
+        }
+    }
+    32.0 * sum
+}
+
+/// Cellular (Worley) noise: distances to the nearest and second nearest
+/// feature point, one random point per unit cell
+pub fn worley(p: Vector3, seed: u32) -> (f32, f32) {
+    let (cx, cy, cz) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+    let (mut f1, mut f2) = (f32::INFINITY, f32::INFINITY);
+
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (x, y, z) = (cx + dx, cy + dy, cz + dz);
+                let h = hash(x, y, z, seed);
+                // Tres coordenadas pseudoaleatorias a partir de un hash
+                let jitter = |shift: u32| ((h.rotate_left(shift) & 0xffff) as f32) / 65535.0;
+                let feature = Vector3::new(x as f32 + jitter(0), y as f32 + jitter(11), z as f32 + jitter(22));
+                let distance = (feature - p).length();
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+    }
+    (f1, f2)
+}
+
+/// Fractal Brownian motion: `octaves` layers of Perlin noise, each twice the
+/// frequency and half the amplitude of the previous one. Roughly in [-1, 1].
+pub fn fbm(p: Vector3, octaves: u32, seed: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for octave in 0..octaves {
+        sum += perlin(p * frequency, seed.wrapping_add(octave)) * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+/// Like `fbm` but summing absolute values: billowy, creased patterns
+/// (marble veins, fire, clouds). In [0, 1).
+pub fn turbulence(p: Vector3, octaves: u32, seed: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for octave in 0..octaves {
+        sum += perlin(p * frequency, seed.wrapping_add(octave)).abs() * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+/// Domain warping: displaces `p` by a vector-valued fBm so any pattern
+/// sampled at the result looks swirled and organic
+pub fn domain_warp(p: Vector3, strength: f32, seed: u32) -> Vector3 {
+    // Desplazamientos distintos por eje para que la deformación no sea diagonal
+    let offset = Vector3::new(
+        fbm(p, 3, seed),
+        fbm(p + Vector3::new(5.2, 1.3, 7.1), 3, seed),
+        fbm(p + Vector3::new(1.7, 9.2, 3.4), 3, seed),
+    );
+    p + offset * strength
+}
@@ -0,0 +1,74 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+
+/// Builds an orthonormal (tangent, bitangent) pair lying on the surface with the given normal
+pub fn planar_basis(normal: &Vector3) -> (Vector3, Vector3) {
+    // Choose a helper axis that is not parallel to the normal
+    let helper = if normal.x.abs() > 0.9 {
+        Vector3::new(0.0, 0.0, 1.0)
+    } else {
+        Vector3::new(1.0, 0.0, 0.0)
+    };
+
+    // Gram-Schmidt: remove the normal component from the helper
+    let tangent = (helper - *normal * helper.dot(*normal)).normalized();
+    let bitangent = tangent.cross(*normal);
+    (tangent, bitangent)
+}
+
+/// Infinite plane defined by a point on it and its normal
+#[derive(Clone)]
+pub struct Plane {
+    pub point: Vector3,
+    pub normal: Vector3,
+    pub material: Material,
+    pub tiling: (f32, f32), // Repeticiones de textura por unidad de mundo (u, v)
+}
+
+impl Plane {
+    pub fn new(point: Vector3, normal: Vector3, material: Material) -> Self {
+        Plane {
+            point,
+            normal: normal.normalized(),
+            material,
+            tiling: (0.5, 0.5), // Una repetición cada 2 unidades, igual que los cubos del terreno
+        }
+    }
+
+    pub fn with_tiling(mut self, u_repeat: f32, v_repeat: f32) -> Self {
+        self.tiling = (u_repeat, v_repeat);
+        self
+    }
+
+    fn get_uv(&self, point: &Vector3) -> (f32, f32) {
+        let (tangent, bitangent) = planar_basis(&self.normal);
+        let local_point = *point - self.point;
+
+        // Proyección planar con repetición (la textura se envuelve en [0, 1))
+        let u = (local_point.dot(tangent) * self.tiling.0).rem_euclid(1.0);
+        let v = (local_point.dot(bitangent) * self.tiling.1).rem_euclid(1.0);
+        (u, 1.0 - v)
+    }
+}
+
+impl RayIntersect for Plane {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let denom = self.normal.dot(*ray_direction);
+
+        // Ray parallel to the plane
+        if denom.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+
+        let t = (self.point - *ray_origin).dot(self.normal) / denom;
+        if t <= 0.0 {
+            return Intersect::empty();
+        }
+
+        let point = *ray_origin + *ray_direction * t;
+        let (u, v) = self.get_uv(&point);
+
+        Intersect::new(point, self.normal, t, self.material.clone(), u, v)
+    }
+}
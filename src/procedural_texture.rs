@@ -0,0 +1,122 @@
+use raylib::prelude::Vector3;
+use crate::noise::{domain_warp, fbm, perlin, simplex, turbulence, worley};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum NoisePattern {
+    Perlin,
+    Simplex,
+    Worley,      // Distancia al punto característico más cercano (celdas)
+    WorleyEdges, // F2 - F1: líneas entre celdas (grietas, adoquines)
+    Fbm,
+    Turbulence,
+}
+
+/// Which hit point feeds the pattern: `World` stays fixed while objects move
+/// through it, `Object` sticks to the object's own (untransformed) space.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextureSpace {
+    World,
+    Object,
+}
+
+/// Noise-based texture evaluated directly at 3D hit points, so it needs no
+/// UVs and never shows seams. Values are in [0, 1]; `color` maps them onto
+/// a two-colour ramp.
+#[derive(Clone, Copy)]
+pub struct ProceduralTexture {
+    pub pattern: NoisePattern,
+    pub scale: f32,    // Frecuencia: repeticiones por unidad
+    pub octaves: u32,  // Solo para Fbm y Turbulence
+    pub seed: u32,
+    pub warp: f32,     // Intensidad del domain warping (0 = sin deformar)
+    pub space: TextureSpace,
+    pub velocity: Vector3, // El patrón se desplaza con el tiempo (unidades por segundo)
+    pub color_low: Vector3,
+    pub color_high: Vector3,
+}
+
+impl ProceduralTexture {
+    pub fn new(pattern: NoisePattern, scale: f32) -> Self {
+        ProceduralTexture {
+            pattern,
+            scale,
+            octaves: 4,
+            seed: 0,
+            warp: 0.0,
+            space: TextureSpace::World,
+            velocity: Vector3::zero(),
+            color_low: Vector3::zero(),
+            color_high: Vector3::one(),
+        }
+    }
+
+    pub fn with_octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_warp(mut self, warp: f32) -> Self {
+        self.warp = warp;
+        self
+    }
+
+    pub fn in_object_space(mut self) -> Self {
+        self.space = TextureSpace::Object;
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: Vector3) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_colors(mut self, color_low: Vector3, color_high: Vector3) -> Self {
+        self.color_low = color_low;
+        self.color_high = color_high;
+        self
+    }
+
+    // Punto de muestreo según el espacio elegido, arrastrado por la velocidad
+    fn sample_point(&self, point: &Vector3, object_point: &Vector3, time: f32) -> Vector3 {
+        let point = match self.space {
+            TextureSpace::World => *point,
+            TextureSpace::Object => *object_point,
+        };
+        point - self.velocity * time
+    }
+
+    // Valor del patrón en un punto ya expresado en el espacio de la textura
+    fn evaluate(&self, p: Vector3) -> f32 {
+        let mut p = p * self.scale;
+        if self.warp != 0.0 {
+            p = domain_warp(p, self.warp, self.seed);
+        }
+        let value = match self.pattern {
+            NoisePattern::Perlin => perlin(p, self.seed) * 0.5 + 0.5,
+            NoisePattern::Simplex => simplex(p, self.seed) * 0.5 + 0.5,
+            NoisePattern::Worley => worley(p, self.seed).0,
+            NoisePattern::WorleyEdges => {
+                let (f1, f2) = worley(p, self.seed);
+                f2 - f1
+            }
+            NoisePattern::Fbm => fbm(p, self.octaves, self.seed) + 0.5,
+            NoisePattern::Turbulence => turbulence(p, self.octaves, self.seed) * 1.5,
+        };
+        value.clamp(0.0, 1.0)
+    }
+
+    /// Pattern value at a hit point, `time` seconds into the animation
+    pub fn value(&self, point: &Vector3, object_point: &Vector3, time: f32) -> f32 {
+        self.evaluate(self.sample_point(point, object_point, time))
+    }
+
+    pub fn color(&self, point: &Vector3, object_point: &Vector3, time: f32) -> Vector3 {
+        let t = self.value(point, object_point, time);
+        self.color_low + (self.color_high - self.color_low) * t
+    }
+}
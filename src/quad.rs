@@ -0,0 +1,74 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+
+/// Finite parallelogram spanned by two edge vectors from a corner
+#[derive(Clone)]
+pub struct Quad {
+    pub corner: Vector3,
+    pub edge_u: Vector3,
+    pub edge_v: Vector3,
+    pub material: Material,
+    pub tiling: (f32, f32), // Repeticiones de textura a lo largo de cada borde
+    normal: Vector3,
+    w: Vector3, // n / (n · n), usado para obtener las coordenadas planas del impacto
+}
+
+impl Quad {
+    pub fn new(corner: Vector3, edge_u: Vector3, edge_v: Vector3, material: Material) -> Self {
+        let n = edge_u.cross(edge_v);
+        Quad {
+            corner,
+            edge_u,
+            edge_v,
+            material,
+            tiling: (1.0, 1.0),
+            normal: n.normalized(),
+            w: n / n.dot(n),
+        }
+    }
+
+    /// Axis-aligned horizontal quad centered at `center`, facing up (ideal for water and floors)
+    pub fn horizontal(center: Vector3, width: f32, depth: f32, material: Material) -> Self {
+        let corner = center - Vector3::new(width / 2.0, 0.0, depth / 2.0);
+        // edge_u (Z) × edge_v (X) apunta hacia +Y
+        Quad::new(corner, Vector3::new(0.0, 0.0, depth), Vector3::new(width, 0.0, 0.0), material)
+    }
+
+    pub fn with_tiling(mut self, u_repeat: f32, v_repeat: f32) -> Self {
+        self.tiling = (u_repeat, v_repeat);
+        self
+    }
+}
+
+impl RayIntersect for Quad {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let denom = self.normal.dot(*ray_direction);
+
+        // Ray parallel to the quad
+        if denom.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+
+        let t = (self.corner - *ray_origin).dot(self.normal) / denom;
+        if t <= 0.0 {
+            return Intersect::empty();
+        }
+
+        let point = *ray_origin + *ray_direction * t;
+
+        // Coordenadas del impacto en la base (edge_u, edge_v)
+        let planar_hit = point - self.corner;
+        let alpha = self.w.dot(planar_hit.cross(self.edge_v));
+        let beta = self.w.dot(self.edge_u.cross(planar_hit));
+
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return Intersect::empty();
+        }
+
+        let u = (alpha * self.tiling.0).rem_euclid(1.0);
+        let v = (beta * self.tiling.1).rem_euclid(1.0);
+
+        Intersect::new(point, self.normal, t, self.material.clone(), u, 1.0 - v)
+    }
+}
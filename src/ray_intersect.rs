@@ -10,6 +10,8 @@ pub struct Intersect {
     pub material: Material,
     pub u: f32,
     pub v: f32,
+    pub tangent: Option<Vector3>, // Dirección de u creciente en la superficie (para normal maps)
+    pub object_point: Vector3,    // Punto en el espacio propio del objeto (los envoltorios no lo transforman)
 }
 
 impl Intersect {
@@ -29,9 +31,16 @@ impl Intersect {
             material,
             u,
             v,
+            tangent: None,
+            object_point: point,
         }
     }
 
+    pub fn with_tangent(mut self, tangent: Vector3) -> Self {
+        self.tangent = Some(tangent);
+        self
+    }
+
     pub fn empty() -> Self {
         Intersect {
             point: Vector3::zero(),
@@ -41,10 +50,93 @@ impl Intersect {
             material: Material::black(),
             u: 0.0,
             v: 0.0,
+            tangent: None,
+            object_point: Vector3::zero(),
         }
     }
 }
 
+// Avance tras cada cruce al recorrer los intervalos de un objeto
+const INTERVAL_STEP: f32 = 1e-4;
+// Límite de cruces por rayo (evita bucles con geometría degenerada)
+const MAX_INTERVAL_CROSSINGS: usize = 32;
+
+/// Stretch of a ray that lies inside a solid, bounded by its entry and exit hits.
+/// A boundary with `is_intersecting == false` is open: the entry lies behind the
+/// ray origin (distance -inf) or the exit is never reached (distance +inf).
+#[derive(Clone)]
+pub struct Interval {
+    pub enter: Intersect,
+    pub exit: Intersect,
+}
+
+impl Interval {
+    pub fn open_enter() -> Intersect {
+        Intersect { distance: f32::NEG_INFINITY, ..Intersect::empty() }
+    }
+
+    pub fn open_exit() -> Intersect {
+        Intersect { distance: f32::INFINITY, ..Intersect::empty() }
+    }
+}
+
 pub trait RayIntersect {
     fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect;
+
+    /// Intersection for a ray taken at `time` within the shutter interval
+    /// (0 = shutter opens, 1 = it closes). Static objects ignore the time.
+    fn ray_intersect_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, _time: f32) -> Intersect {
+        self.ray_intersect(ray_origin, ray_direction)
+    }
+
+    /// All intervals where the ray is inside the object, sorted by distance.
+    /// The default walks successive hits of `ray_intersect`, treating hits whose
+    /// normal faces the ray as entries and the rest as exits, which is correct
+    /// for any closed solid with outward normals.
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<Interval> {
+        let mut intervals = Vec::new();
+        let mut enter: Option<Intersect> = None;
+        let mut travelled = 0.0;
+
+        for _ in 0..MAX_INTERVAL_CROSSINGS {
+            let origin = *ray_origin + *ray_direction * travelled;
+            let mut hit = self.ray_intersect(&origin, ray_direction);
+            if !hit.is_intersecting {
+                break;
+            }
+
+            // Distancias siempre relativas al origen original del rayo
+            hit.distance += travelled;
+            travelled = hit.distance + INTERVAL_STEP;
+
+            if hit.normal.dot(*ray_direction) < 0.0 {
+                enter = Some(hit);
+            } else {
+                let enter = enter.take().unwrap_or_else(Interval::open_enter);
+                intervals.push(Interval { enter, exit: hit });
+            }
+        }
+
+        if let Some(enter) = enter {
+            intervals.push(Interval { enter, exit: Interval::open_exit() });
+        }
+
+        intervals
+    }
+}
+
+// Un objeto prestado se intersecta igual que el original: permite poner en la
+// vista del frame los objetos base que no se mueven sin envolverlos
+impl<T: RayIntersect + ?Sized> RayIntersect for &T {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        (**self).ray_intersect(ray_origin, ray_direction)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        (**self).ray_intersect_at(ray_origin, ray_direction, time)
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<Interval> {
+        (**self).ray_intervals(ray_origin, ray_direction)
+    }
 }
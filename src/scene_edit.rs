@@ -0,0 +1,632 @@
+use raylib::prelude::Vector3;
+use std::fs;
+use std::path::Path;
+use crate::capsule::Capsule;
+use crate::cone::Cone;
+use crate::cube::Cube;
+use crate::cylinder::Cylinder;
+use crate::disk::Disk;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, Interval, RayIntersect};
+use crate::transform::Transform;
+
+// Tamaño de referencia de las primitivas nuevas (se ajusta luego con la escala)
+const PRIMITIVE_SIZE: f32 = 0.5;
+// Separación de una copia respecto al original, para que no se solapen
+const DUPLICATE_OFFSET: Vector3 = Vector3::new(1.0, 0.0, 0.0);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PrimitiveKind {
+    Cube,
+    Cylinder,
+    Cone,
+    Capsule,
+    Disk,
+}
+
+impl PrimitiveKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrimitiveKind::Cube => "cube",
+            PrimitiveKind::Cylinder => "cylinder",
+            PrimitiveKind::Cone => "cone",
+            PrimitiveKind::Capsule => "capsule",
+            PrimitiveKind::Disk => "disk",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            PrimitiveKind::Cube => PrimitiveKind::Cylinder,
+            PrimitiveKind::Cylinder => PrimitiveKind::Cone,
+            PrimitiveKind::Cone => PrimitiveKind::Capsule,
+            PrimitiveKind::Capsule => PrimitiveKind::Disk,
+            PrimitiveKind::Disk => PrimitiveKind::Cube,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [PrimitiveKind::Cube, PrimitiveKind::Cylinder, PrimitiveKind::Cone, PrimitiveKind::Capsule, PrimitiveKind::Disk]
+            .into_iter()
+            .find(|kind| kind.name() == name)
+    }
+}
+
+/// Object added from the editor, kept as data so undo and the scene file can
+/// rebuild it
+#[derive(Clone, Copy)]
+struct Primitive {
+    kind: PrimitiveKind,
+    position: Vector3, // Centro de la base (centro a secas en cubo y disco)
+    material: usize,   // Índice en la lista de materiales del editor
+}
+
+impl Primitive {
+    fn build(&self, materials: &[NamedMaterial]) -> Box<dyn RayIntersect> {
+        // Como lo crea su constructor: los ajustes se aplican al trazar, como en la escena
+        let material = materials[self.material].original.clone();
+        let (position, size) = (self.position, PRIMITIVE_SIZE);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        match self.kind {
+            PrimitiveKind::Cube => Box::new(Cube::new(position, size, material)),
+            PrimitiveKind::Cylinder => Box::new(Cylinder::vertical(position, size * 0.5, size, material)),
+            PrimitiveKind::Cone => Box::new(Cone::vertical(position, size * 0.5, size, material)),
+            PrimitiveKind::Capsule => Box::new(Capsule::new(position, position + up * size, size * 0.25, material)),
+            PrimitiveKind::Disk => Box::new(Disk::new(position, up, size, material)),
+        }
+    }
+}
+
+/// Material the editor can assign, one per constructor in `Material::presets()`.
+/// Tuning changes it in place, so every object using it follows: objects
+/// built in code are recognized by the settings their constructor gave them.
+#[derive(Clone)]
+pub struct NamedMaterial {
+    pub name: String,
+    original: Material, // Tal como lo crea el constructor `name`
+    pub material: Material,
+}
+
+impl NamedMaterial {
+    pub fn is_tuned(&self) -> bool {
+        !same_settings(&self.original, &self.material)
+    }
+
+    /// Gives `material` the tuned settings, keeping its graph and UV flow
+    pub fn tune(&self, material: &mut Material) {
+        copy_settings(&self.material, material);
+    }
+}
+
+// Lo que cambia el editor de materiales: con los mismos valores, dos
+// materiales salen del mismo constructor
+fn same_settings(a: &Material, b: &Material) -> bool {
+    a.diffuse == b.diffuse
+        && a.albedo == b.albedo
+        && a.specular == b.specular
+        && a.refractive_index == b.refractive_index
+        && a.texture_id == b.texture_id
+        && a.normal_map_id == b.normal_map_id
+}
+
+fn copy_settings(from: &Material, to: &mut Material) {
+    to.diffuse = from.diffuse;
+    to.albedo = from.albedo;
+    to.specular = from.specular;
+    to.refractive_index = from.refractive_index;
+    to.texture_id = from.texture_id.clone();
+    to.normal_map_id = from.normal_map_id.clone();
+}
+
+/// One object of the edited scene: which base object it shows and the
+/// changes made on top of it. Copies share their source object.
+#[derive(Clone, Copy)]
+pub struct ObjectEdit {
+    pub source: usize,           // Índice en la lista de objetos base
+    pub translation: Vector3,
+    pub scale: f32,
+    pub pivot: Vector3,          // Punto fijo al escalar
+    pub material: Option<usize>, // Material de la lista del editor que sustituye al original
+    pub deleted: bool,
+}
+
+impl ObjectEdit {
+    fn of(source: usize) -> Self {
+        ObjectEdit {
+            source,
+            translation: Vector3::zero(),
+            scale: 1.0,
+            pivot: Vector3::zero(),
+            material: None,
+            deleted: false,
+        }
+    }
+
+    /// Placement change, applied after the object's own animation
+    pub fn transform(&self) -> Transform {
+        Transform::around_pivot(self.pivot, self.translation, Vector3::zero(), self.scale)
+    }
+
+    /// Whether the object was moved or scaled from where the code placed it
+    pub fn is_moved(&self) -> bool {
+        self.translation != Vector3::zero() || self.scale != 1.0
+    }
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    slots: Vec<ObjectEdit>,
+    additions: Vec<Primitive>,
+    materials: Vec<NamedMaterial>,
+}
+
+/// Interactive edits over the scene built in code. Object indices never
+/// shift: deleting only hides, and copies and new primitives go at the end,
+/// so animations and the selection keep pointing at the same objects.
+pub struct SceneEditor {
+    generated: usize, // Objetos base creados por el código; los añadidos van detrás
+    slots: Vec<ObjectEdit>,
+    additions: Vec<Primitive>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    materials: Vec<NamedMaterial>,
+}
+
+impl SceneEditor {
+    pub fn new(generated: usize) -> Self {
+        SceneEditor {
+            generated,
+            slots: (0..generated).map(ObjectEdit::of).collect(),
+            additions: Vec::new(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            materials: Material::presets()
+                .into_iter()
+                .map(|(name, material)| NamedMaterial { name: name.to_string(), original: material.clone(), material })
+                .collect(),
+        }
+    }
+
+    pub fn slots(&self) -> &[ObjectEdit] {
+        &self.slots
+    }
+
+    pub fn material_name(&self, material: usize) -> &str {
+        &self.materials[material].name
+    }
+
+    /// Named materials changed in the app
+    pub fn tuned_materials(&self) -> impl Iterator<Item = &NamedMaterial> {
+        self.materials.iter().filter(|named| named.is_tuned())
+    }
+
+    /// Material replacing the original one of the object at `index`, if any
+    pub fn material(&self, index: usize) -> Option<&Material> {
+        self.slots[index].material.map(|material| &self.materials[material].material)
+    }
+
+    /// Named material of the object at `index`: the one assigned in the
+    /// editor or, for objects built in code, the one whose constructor made
+    /// `original` (its material before tuning)
+    pub fn named_material(&self, index: usize, original: &Material) -> Option<usize> {
+        self.slots[index]
+            .material
+            .or_else(|| self.materials.iter().position(|named| same_settings(&named.original, original)))
+    }
+
+    /// Named material `material`, for live changes; call `checkpoint` first
+    pub fn named_material_mut(&mut self, material: usize) -> &mut Material {
+        &mut self.materials[material].material
+    }
+
+    /// How `material`, built in code, looks with the tuned materials
+    pub fn retune(&self, material: &mut Material) {
+        if let Some(named) = self.materials.iter().find(|named| same_settings(&named.original, material) && named.is_tuned()) {
+            named.tune(material);
+        }
+    }
+
+    /// Inverse of `retune`: the material a tuned one was built as
+    pub fn untune(&self, material: &mut Material) {
+        if let Some(named) = self.materials.iter().find(|named| same_settings(&named.material, material) && named.is_tuned()) {
+            copy_settings(&named.original, material);
+        }
+    }
+
+    /// Records the current state as an undo step; call before each change
+    pub fn checkpoint(&mut self) {
+        self.undo.push(self.snapshot());
+        self.redo.clear();
+    }
+
+    /// Starts moving or scaling an object: records an undo step and, if it
+    /// has not been moved yet, makes `pivot` its scaling center
+    pub fn grab(&mut self, index: usize, pivot: Vector3) {
+        self.checkpoint();
+        let slot = &mut self.slots[index];
+        if !slot.is_moved() {
+            slot.pivot = pivot;
+        }
+    }
+
+    pub fn translate(&mut self, index: usize, offset: Vector3) {
+        self.slots[index].translation += offset;
+    }
+
+    pub fn scale(&mut self, index: usize, factor: f32) {
+        let slot = &mut self.slots[index];
+        slot.scale = (slot.scale * factor).clamp(0.05, 20.0);
+    }
+
+    /// Copy of the object at `index`, placed beside it; returns the copy's index
+    pub fn duplicate(&mut self, index: usize) -> usize {
+        self.checkpoint();
+        let mut copy = self.slots[index];
+        copy.translation += DUPLICATE_OFFSET;
+        self.slots.push(copy);
+        self.slots.len() - 1
+    }
+
+    pub fn delete(&mut self, index: usize) {
+        self.checkpoint();
+        self.slots[index].deleted = true;
+    }
+
+    /// Moves the object at `index` `step` places along the material list
+    pub fn cycle_material(&mut self, index: usize, step: isize) {
+        self.checkpoint();
+        let count = self.materials.len() as isize;
+        let slot = &mut self.slots[index];
+        let next = match slot.material {
+            Some(current) => current as isize + step,
+            None if step > 0 => 0,
+            None => count - 1,
+        };
+        slot.material = Some(next.rem_euclid(count) as usize);
+    }
+
+    /// Adds a new primitive standing at `position`; returns its index
+    pub fn add(&mut self, kind: PrimitiveKind, position: Vector3, base_objects: &mut Vec<Box<dyn RayIntersect>>) -> usize {
+        self.checkpoint();
+        let primitive = Primitive { kind, position, material: 0 };
+        base_objects.push(primitive.build(&self.materials));
+        self.additions.push(primitive);
+        self.slots.push(ObjectEdit::of(base_objects.len() - 1));
+        self.slots.len() - 1
+    }
+
+    pub fn undo(&mut self, base_objects: &mut Vec<Box<dyn RayIntersect>>) -> bool {
+        let Some(snapshot) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(self.snapshot());
+        self.restore(snapshot, base_objects);
+        true
+    }
+
+    pub fn redo(&mut self, base_objects: &mut Vec<Box<dyn RayIntersect>>) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(self.snapshot());
+        self.restore(snapshot, base_objects);
+        true
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            slots: self.slots.clone(),
+            additions: self.additions.clone(),
+            materials: self.materials.clone(),
+        }
+    }
+
+    // Los objetos añadidos se reconstruyen desde sus datos tras los generados
+    fn restore(&mut self, snapshot: Snapshot, base_objects: &mut Vec<Box<dyn RayIntersect>>) {
+        base_objects.truncate(self.generated);
+        base_objects.extend(snapshot.additions.iter().map(|primitive| primitive.build(&snapshot.materials)));
+        self.slots = snapshot.slots;
+        self.additions = snapshot.additions;
+        self.materials = snapshot.materials;
+    }
+
+    /// Reads the edits saved at `path` and applies them over the generated
+    /// scene; a missing file just means no edits yet. Lines are, in order:
+    /// `material NAME DR DG DB SPECULAR A0 A1 A2 A3 IOR TEXTURE|- NORMAL|-`
+    /// for tuned materials, `add KIND X Y Z MATERIAL` for new primitives and
+    /// `object INDEX SOURCE TX TY TZ SCALE PX PY PZ MATERIAL|- visible|deleted`
+    /// for every changed or added object. `#` starts a comment line.
+    pub fn load(&mut self, path: &Path, base_objects: &mut Vec<Box<dyn RayIntersect>>) -> Result<(), String> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut slots = self.slots[..self.generated].to_vec();
+        let mut additions = Vec::new();
+        let mut materials: Vec<NamedMaterial> = self
+            .materials
+            .iter()
+            .map(|named| NamedMaterial { material: named.original.clone(), ..named.clone() })
+            .collect();
+
+        for (number, line) in text.lines().enumerate() {
+            let invalid = || format!("{}:{}: Invalid line '{}'", path.display(), number + 1, line);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| fields[i].parse::<f32>().map_err(|_| invalid());
+            let vector = |i: usize| Ok::<_, String>(Vector3::new(number(i)?, number(i + 1)?, number(i + 2)?));
+            let find = |materials: &[NamedMaterial], name: &str| materials.iter().position(|material| material.name == name).ok_or_else(invalid);
+            let optional = |id: &str| (id != "-").then(|| id.to_string());
+
+            match fields[..] {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["material", name, _, _, _, _, _, _, _, _, _, texture, normal_map] => {
+                    let index = find(&materials, name)?;
+                    let material = &mut materials[index].material;
+                    material.diffuse = vector(2)?;
+                    material.specular = number(5)?;
+                    material.albedo = [number(6)?, number(7)?, number(8)?, number(9)?];
+                    material.refractive_index = number(10)?;
+                    material.texture_id = optional(texture);
+                    material.normal_map_id = optional(normal_map);
+                }
+                ["add", kind, _, _, _, material] => additions.push(Primitive {
+                    kind: PrimitiveKind::from_name(kind).ok_or_else(invalid)?,
+                    position: vector(2)?,
+                    material: find(&materials, material)?,
+                }),
+                ["object", index, source, _, _, _, _, _, _, _, material, state] => {
+                    let index: usize = index.parse().map_err(|_| invalid())?;
+                    let source: usize = source.parse().map_err(|_| invalid())?;
+                    if index > slots.len() || source >= self.generated + additions.len() {
+                        return Err(invalid());
+                    }
+                    let edit = ObjectEdit {
+                        source,
+                        translation: vector(3)?,
+                        scale: number(6)?,
+                        pivot: vector(7)?,
+                        material: if material == "-" { None } else { Some(find(&materials, material)?) },
+                        deleted: match state {
+                            "visible" => false,
+                            "deleted" => true,
+                            _ => return Err(invalid()),
+                        },
+                    };
+                    if index == slots.len() {
+                        slots.push(edit);
+                    } else {
+                        slots[index] = edit;
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        self.restore(Snapshot { slots, additions, materials }, base_objects);
+        self.undo.clear();
+        self.redo.clear();
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut text = String::from("# Ediciones de la escena: materiales ajustados, primitivas añadidas y objetos cambiados\n");
+        for tuned in self.tuned_materials() {
+            let (m, optional) = (&tuned.material, |id: &Option<String>| id.clone().unwrap_or_else(|| "-".to_string()));
+            text.push_str(&format!(
+                "material {} {} {} {} {} {} {} {} {} {} {} {}\n",
+                tuned.name,
+                m.diffuse.x, m.diffuse.y, m.diffuse.z,
+                m.specular,
+                m.albedo[0], m.albedo[1], m.albedo[2], m.albedo[3],
+                m.refractive_index,
+                optional(&m.texture_id),
+                optional(&m.normal_map_id),
+            ));
+        }
+        for primitive in &self.additions {
+            let p = primitive.position;
+            text.push_str(&format!("add {} {} {} {} {}\n", primitive.kind.name(), p.x, p.y, p.z, self.material_name(primitive.material)));
+        }
+
+        // Solo los objetos que difieren de los generados, más todos los añadidos
+        for (index, slot) in self.slots.iter().enumerate() {
+            let changed = slot.is_moved() || slot.material.is_some() || slot.deleted;
+            if index < self.generated && slot.source == index && !changed {
+                continue;
+            }
+            let (t, p) = (slot.translation, slot.pivot);
+            let material = slot.material.map_or("-", |material| self.material_name(material));
+            let state = if slot.deleted { "deleted" } else { "visible" };
+            text.push_str(&format!(
+                "object {} {} {} {} {} {} {} {} {} {} {}\n",
+                index, slot.source, t.x, t.y, t.z, slot.scale, p.x, p.y, p.z, material, state
+            ));
+        }
+        fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Object shown with a different material: every hit takes `material`
+pub struct MaterialOverride<'a> {
+    object: Box<dyn RayIntersect + 'a>,
+    material: Material,
+}
+
+impl<'a> MaterialOverride<'a> {
+    pub fn new(object: Box<dyn RayIntersect + 'a>, material: Material) -> Self {
+        MaterialOverride { object, material }
+    }
+}
+
+impl RayIntersect for MaterialOverride<'_> {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        let mut intersect = self.object.ray_intersect_at(ray_origin, ray_direction, time);
+        if intersect.is_intersecting {
+            intersect.material = self.material.clone();
+        }
+        intersect
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<Interval> {
+        let mut intervals = self.object.ray_intervals(ray_origin, ray_direction);
+        for interval in &mut intervals {
+            for boundary in [&mut interval.enter, &mut interval.exit] {
+                if boundary.is_intersecting {
+                    boundary.material = self.material.clone();
+                }
+            }
+        }
+        intervals
+    }
+}
+
+/// Object built in code shown with the tuned materials: hits whose material
+/// comes from a tuned constructor take its settings
+pub struct Retuned<'a> {
+    object: Box<dyn RayIntersect + 'a>,
+    editor: &'a SceneEditor,
+}
+
+impl<'a> Retuned<'a> {
+    pub fn new(object: Box<dyn RayIntersect + 'a>, editor: &'a SceneEditor) -> Self {
+        Retuned { object, editor }
+    }
+}
+
+impl RayIntersect for Retuned<'_> {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        let mut intersect = self.object.ray_intersect_at(ray_origin, ray_direction, time);
+        if intersect.is_intersecting {
+            self.editor.retune(&mut intersect.material);
+        }
+        intersect
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<Interval> {
+        let mut intervals = self.object.ray_intervals(ray_origin, ray_direction);
+        for interval in &mut intervals {
+            for boundary in [&mut interval.enter, &mut interval.exit] {
+                if boundary.is_intersecting {
+                    self.editor.retune(&mut boundary.material);
+                }
+            }
+        }
+        intervals
+    }
+}
+
+/// Stand-in for a deleted object, keeping the indices of the rest
+pub struct Removed;
+
+impl RayIntersect for Removed {
+    fn ray_intersect(&self, _ray_origin: &Vector3, _ray_direction: &Vector3) -> Intersect {
+        Intersect::empty()
+    }
+
+    fn ray_intervals(&self, _ray_origin: &Vector3, _ray_direction: &Vector3) -> Vec<Interval> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene(count: usize) -> Vec<Box<dyn RayIntersect>> {
+        (0..count)
+            .map(|i| Box::new(Cube::new(Vector3::new(i as f32 * 2.0, 0.0, 0.0), 0.5, Material::black())) as Box<dyn RayIntersect>)
+            .collect()
+    }
+
+    // Índice de origen y estado de borrado de cada objeto
+    fn layout(editor: &SceneEditor) -> Vec<(usize, bool)> {
+        editor.slots().iter().map(|slot| (slot.source, slot.deleted)).collect()
+    }
+
+    #[test]
+    fn indices_survive_delete_duplicate_undo_and_redo() {
+        let mut objects = scene(3);
+        let mut editor = SceneEditor::new(objects.len());
+
+        editor.delete(1);
+        let copy = editor.duplicate(2);
+        assert_eq!(copy, 3);
+        let added = editor.add(PrimitiveKind::Cone, Vector3::zero(), &mut objects);
+        assert_eq!(added, 4);
+        assert_eq!(objects.len(), 4);
+        assert_eq!(layout(&editor), [(0, false), (1, true), (2, false), (2, false), (3, false)]);
+
+        assert!(editor.undo(&mut objects));
+        assert!(editor.undo(&mut objects));
+        assert_eq!(layout(&editor), [(0, false), (1, true), (2, false)]);
+        assert_eq!(objects.len(), 3);
+
+        assert!(editor.redo(&mut objects));
+        assert!(editor.redo(&mut objects));
+        assert!(!editor.redo(&mut objects));
+        assert_eq!(layout(&editor), [(0, false), (1, true), (2, false), (2, false), (3, false)]);
+        assert_eq!(objects.len(), 4);
+    }
+
+    #[test]
+    fn tuning_follows_every_object_of_the_material() {
+        let mut editor = SceneEditor::new(2);
+        let (_, agua) = Material::presets().into_iter().find(|(name, _)| *name == "agua").unwrap();
+        let named = editor.named_material(0, &agua).unwrap();
+        assert_eq!(editor.named_material(1, &agua), Some(named));
+
+        editor.checkpoint();
+        editor.named_material_mut(named).diffuse = Vector3::new(1.0, 0.0, 0.0);
+        let mut seen = agua.clone().with_uv_flow(0.0, 0.1);
+        editor.retune(&mut seen);
+        assert_eq!(seen.diffuse, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(seen.uv_flow, (0.0, 0.1));
+
+        editor.untune(&mut seen);
+        assert_eq!(seen.diffuse, agua.diffuse);
+    }
+
+    #[test]
+    fn edits_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("scene_edits_test_{}.txt", std::process::id()));
+        let mut objects = scene(3);
+        let mut editor = SceneEditor::new(objects.len());
+
+        editor.grab(0, Vector3::new(0.0, 0.5, 0.0));
+        editor.translate(0, Vector3::new(1.0, 2.0, 3.0));
+        editor.scale(0, 2.0);
+        editor.cycle_material(2, 1);
+        editor.delete(1);
+        editor.duplicate(0);
+        editor.add(PrimitiveKind::Capsule, Vector3::new(-1.0, 0.0, 4.0), &mut objects);
+        editor.named_material_mut(1).specular = 42.0;
+        editor.save(&path).unwrap();
+
+        let mut loaded_objects = scene(3);
+        let mut loaded = SceneEditor::new(loaded_objects.len());
+        loaded.load(&path, &mut loaded_objects).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded_objects.len(), objects.len());
+        assert_eq!(layout(&loaded), layout(&editor));
+        for (loaded, original) in loaded.slots().iter().zip(editor.slots()) {
+            assert_eq!(loaded.translation, original.translation);
+            assert_eq!(loaded.scale, original.scale);
+            assert_eq!(loaded.pivot, original.pivot);
+            assert_eq!(loaded.material, original.material);
+        }
+        let tuned: Vec<(&str, f32)> = loaded.tuned_materials().map(|named| (named.name.as_str(), named.material.specular)).collect();
+        assert_eq!(tuned, [(editor.material_name(1), 42.0)]);
+        // Lo cargado no se puede deshacer: es el punto de partida
+        assert!(!loaded.undo(&mut loaded_objects));
+    }
+}
@@ -0,0 +1,264 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+
+// Parámetros del sphere tracing
+const MAX_STEPS: u32 = 128;
+const SURFACE_EPSILON: f32 = 1e-3;
+const NORMAL_EPSILON: f32 = 1e-3;
+
+/// Implicit surface described by a signed distance function (negative inside)
+#[derive(Clone)]
+pub enum SdfShape {
+    Sphere { center: Vector3, radius: f32 },
+    RoundBox { center: Vector3, half_extents: Vector3, radius: f32 },
+    Torus { center: Vector3, major_radius: f32, minor_radius: f32 }, // Anillo en el plano XZ
+    Union(Box<SdfShape>, Box<SdfShape>),
+    SmoothUnion(Box<SdfShape>, Box<SdfShape>, f32),
+    SmoothSubtraction(Box<SdfShape>, Box<SdfShape>, f32), // Primero menos segundo
+    SmoothIntersection(Box<SdfShape>, Box<SdfShape>, f32),
+    Displace(Box<SdfShape>, f32, f32), // Amplitud y frecuencia de una ondulación senoidal
+}
+
+impl SdfShape {
+    pub fn sphere(center: Vector3, radius: f32) -> Self {
+        SdfShape::Sphere { center, radius }
+    }
+
+    /// Box with edges rounded by `radius` (use 0.0 for sharp edges)
+    pub fn round_box(center: Vector3, half_extents: Vector3, radius: f32) -> Self {
+        SdfShape::RoundBox { center, half_extents, radius }
+    }
+
+    pub fn torus(center: Vector3, major_radius: f32, minor_radius: f32) -> Self {
+        SdfShape::Torus { center, major_radius, minor_radius }
+    }
+
+    pub fn union(self, other: SdfShape) -> Self {
+        SdfShape::Union(Box::new(self), Box::new(other))
+    }
+
+    /// Blends both shapes over a distance of roughly `k`
+    pub fn smooth_union(self, other: SdfShape, k: f32) -> Self {
+        SdfShape::SmoothUnion(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn smooth_subtraction(self, other: SdfShape, k: f32) -> Self {
+        SdfShape::SmoothSubtraction(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn smooth_intersection(self, other: SdfShape, k: f32) -> Self {
+        SdfShape::SmoothIntersection(Box::new(self), Box::new(other), k)
+    }
+
+    /// Wobbles the surface, useful for eroded stone
+    pub fn displace(self, amplitude: f32, frequency: f32) -> Self {
+        SdfShape::Displace(Box::new(self), amplitude, frequency)
+    }
+
+    pub fn distance(&self, p: &Vector3) -> f32 {
+        match self {
+            SdfShape::Sphere { center, radius } => (*p - *center).length() - radius,
+            SdfShape::RoundBox { center, half_extents, radius } => {
+                let local = *p - *center;
+                let q = Vector3::new(
+                    local.x.abs() - (half_extents.x - radius),
+                    local.y.abs() - (half_extents.y - radius),
+                    local.z.abs() - (half_extents.z - radius),
+                );
+                let outside = Vector3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+                let inside = q.x.max(q.y).max(q.z).min(0.0);
+                outside + inside - radius
+            }
+            SdfShape::Torus { center, major_radius, minor_radius } => {
+                let local = *p - *center;
+                let ring = (local.x * local.x + local.z * local.z).sqrt() - major_radius;
+                (ring * ring + local.y * local.y).sqrt() - minor_radius
+            }
+            SdfShape::Union(a, b) => a.distance(p).min(b.distance(p)),
+            SdfShape::SmoothUnion(a, b, k) => {
+                let (da, db) = (a.distance(p), b.distance(p));
+                let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                db + (da - db) * h - k * h * (1.0 - h)
+            }
+            SdfShape::SmoothSubtraction(a, b, k) => {
+                let (da, db) = (a.distance(p), b.distance(p));
+                let h = (0.5 - 0.5 * (da + db) / k).clamp(0.0, 1.0);
+                da + (-db - da) * h + k * h * (1.0 - h)
+            }
+            SdfShape::SmoothIntersection(a, b, k) => {
+                let (da, db) = (a.distance(p), b.distance(p));
+                let h = (0.5 - 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                db + (da - db) * h + k * h * (1.0 - h)
+            }
+            SdfShape::Displace(shape, amplitude, frequency) => {
+                let wobble = (p.x * frequency).sin() * (p.y * frequency).sin() * (p.z * frequency).sin();
+                shape.distance(p) + amplitude * wobble
+            }
+        }
+    }
+
+    /// Bounding sphere (center, radius) that contains the whole surface
+    pub fn bounds(&self) -> (Vector3, f32) {
+        match self {
+            SdfShape::Sphere { center, radius } => (*center, *radius),
+            SdfShape::RoundBox { center, half_extents, .. } => (*center, half_extents.length()),
+            SdfShape::Torus { center, major_radius, minor_radius } => (*center, major_radius + minor_radius),
+            SdfShape::Union(a, b) => enclosing_sphere(a.bounds(), b.bounds()),
+            SdfShape::SmoothUnion(a, b, k) => {
+                let (center, radius) = enclosing_sphere(a.bounds(), b.bounds());
+                (center, radius + k)
+            }
+            // Restar o intersectar nunca crece más allá del primer operando
+            SdfShape::SmoothSubtraction(a, _, k) | SdfShape::SmoothIntersection(a, _, k) => {
+                let (center, radius) = a.bounds();
+                (center, radius + k * 0.25)
+            }
+            SdfShape::Displace(shape, amplitude, _) => {
+                let (center, radius) = shape.bounds();
+                (center, radius + amplitude.abs())
+            }
+        }
+    }
+
+    // Cota de la pendiente de la función: los pasos se dividen por ella para no atravesar la superficie
+    fn lipschitz(&self) -> f32 {
+        match self {
+            SdfShape::Sphere { .. } | SdfShape::RoundBox { .. } | SdfShape::Torus { .. } => 1.0,
+            SdfShape::Union(a, b)
+            | SdfShape::SmoothUnion(a, b, _)
+            | SdfShape::SmoothSubtraction(a, b, _)
+            | SdfShape::SmoothIntersection(a, b, _) => a.lipschitz().max(b.lipschitz()),
+            SdfShape::Displace(shape, amplitude, frequency) => {
+                shape.lipschitz() + amplitude.abs() * frequency.abs() * 3.0f32.sqrt()
+            }
+        }
+    }
+}
+
+// Esfera mínima que contiene a otras dos
+fn enclosing_sphere(a: (Vector3, f32), b: (Vector3, f32)) -> (Vector3, f32) {
+    let (center_a, radius_a) = a;
+    let (center_b, radius_b) = b;
+    let offset = center_b - center_a;
+    let distance = offset.length();
+
+    if distance + radius_b <= radius_a {
+        return a;
+    }
+    if distance + radius_a <= radius_b {
+        return b;
+    }
+
+    let radius = (distance + radius_a + radius_b) * 0.5;
+    let center = center_a + offset * ((radius - radius_a) / distance);
+    (center, radius)
+}
+
+/// Scene object rendered by sphere tracing an `SdfShape`
+#[derive(Clone)]
+pub struct SdfObject {
+    pub shape: SdfShape,
+    pub material: Material,
+    bounds: (Vector3, f32),
+    lipschitz: f32,
+}
+
+impl SdfObject {
+    pub fn new(shape: SdfShape, material: Material) -> Self {
+        let bounds = shape.bounds();
+        let lipschitz = shape.lipschitz();
+        SdfObject {
+            shape,
+            material,
+            bounds,
+            lipschitz,
+        }
+    }
+
+    // Intervalo [t0, t1] del rayo dentro de la esfera envolvente
+    fn bounds_range(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, f32)> {
+        let (center, radius) = self.bounds;
+        let oc = *ray_origin - center;
+        let b = oc.dot(*ray_direction);
+        let c = oc.dot(oc) - radius * radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt_d = discriminant.sqrt();
+        let t1 = -b + sqrt_d;
+        if t1 < 0.0 {
+            return None;
+        }
+        Some(((-b - sqrt_d).max(0.0), t1))
+    }
+
+    // Normal a partir del gradiente (diferencias centrales)
+    fn normal_at(&self, p: &Vector3) -> Vector3 {
+        let dx = Vector3::new(NORMAL_EPSILON, 0.0, 0.0);
+        let dy = Vector3::new(0.0, NORMAL_EPSILON, 0.0);
+        let dz = Vector3::new(0.0, 0.0, NORMAL_EPSILON);
+        Vector3::new(
+            self.shape.distance(&(*p + dx)) - self.shape.distance(&(*p - dx)),
+            self.shape.distance(&(*p + dy)) - self.shape.distance(&(*p - dy)),
+            self.shape.distance(&(*p + dz)) - self.shape.distance(&(*p - dz)),
+        )
+        .normalized()
+    }
+
+    // Proyección por eje dominante, una repetición de textura cada 2 unidades como los cubos
+    fn get_uv(&self, point: &Vector3, normal: &Vector3) -> (f32, f32) {
+        let local_point = (*point - self.bounds.0) * 0.5;
+        let (u, v) = if normal.x.abs() > normal.y.abs() && normal.x.abs() > normal.z.abs() {
+            (local_point.z, local_point.y)
+        } else if normal.y.abs() > normal.z.abs() {
+            (local_point.x, local_point.z)
+        } else {
+            (local_point.x, local_point.y)
+        };
+        (u.rem_euclid(1.0), 1.0 - v.rem_euclid(1.0))
+    }
+}
+
+impl RayIntersect for SdfObject {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let Some((t_start, t_end)) = self.bounds_range(ray_origin, ray_direction) else {
+            return Intersect::empty();
+        };
+
+        // Si el rayo nace dentro del sólido (refracción) se busca la salida.
+        // Justo sobre la superficie decide la dirección: alejarse del sólido es estar fuera.
+        let start_distance = self.shape.distance(ray_origin);
+        let inside = if start_distance.abs() < SURFACE_EPSILON * 2.0 {
+            self.normal_at(ray_origin).dot(*ray_direction) < 0.0
+        } else {
+            start_distance < 0.0
+        };
+        let sign = if inside { -1.0 } else { 1.0 };
+
+        let mut t = t_start;
+        for _ in 0..MAX_STEPS {
+            if t > t_end {
+                break;
+            }
+
+            let point = *ray_origin + *ray_direction * t;
+            let distance = sign * self.shape.distance(&point) / self.lipschitz;
+
+            // Ignorar la superficie de la que parte el rayo
+            if distance < SURFACE_EPSILON && t > SURFACE_EPSILON * 2.0 {
+                // Último paso para quedar prácticamente sobre la superficie
+                let t = t + distance;
+                let point = *ray_origin + *ray_direction * t;
+                let normal = self.normal_at(&point);
+                let (u, v) = self.get_uv(&point, &normal);
+                return Intersect::new(point, normal, t, self.material.clone(), u, v);
+            }
+
+            t += distance.max(SURFACE_EPSILON);
+        }
+
+        Intersect::empty()
+    }
+}
@@ -0,0 +1,267 @@
+use raylib::prelude::*;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use crate::camera::{Projection, MAX_FOV, MIN_FOV};
+use crate::material::vector3_to_color;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FrameFormat {
+    Png, // 8 bits por canal, recortado a [0, 1]
+    Exr, // Radiancia lineal en coma flotante, sin recortar
+}
+
+impl FrameFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Png => "png",
+            FrameFormat::Exr => "exr",
+        }
+    }
+}
+
+/// Batch render of an animation: numbered frames written to `output_dir`.
+/// Frames already on disk are skipped, so an interrupted run resumes where
+/// it stopped.
+pub struct SequenceSettings {
+    pub output_dir: PathBuf,
+    pub first_frame: u32,
+    pub last_frame: u32,
+    pub fps: f32,
+    pub width: u32,
+    pub height: u32,
+    pub format: FrameFormat,
+    pub samples: u32,             // Rayos por píxel repartidos en el obturador y la lente
+    pub motion_blur: bool,        // El obturador queda abierto durante `shutter` del frame
+    pub shutter: f32,             // Fracción del frame con el obturador abierto
+    pub turntable: bool,          // La escena da una vuelta completa a lo largo de la secuencia
+    pub camera_animation: bool,   // La cámara sigue sus keyframes (vuelo) en vez de quedarse fija
+    pub camera_path: Option<PathBuf>, // Recorrido de cámara (spline) que sigue la secuencia
+    pub bookmark: Option<String>, // Marcador de cámara guardado desde el que se renderiza
+    pub projection: Option<Projection>,
+    pub fov: Option<f32>,         // Campo de visión vertical en radianes
+    pub aperture: Option<f32>,    // Radio de la lente para la profundidad de campo
+    pub focal_distance: Option<f32>,
+}
+
+impl SequenceSettings {
+    /// Reads the batch options from the command line; `None` when `--sequence`
+    /// is absent and the app should run interactively.
+    ///
+    /// `--sequence DIR [--frames FIRST-LAST] [--fps N] [--size WxH]
+    ///  [--format png|exr] [--samples N] [--motion-blur] [--shutter FRACTION] [--turntable]
+    ///  [--camera-animation] [--camera-path FILE] [--bookmark NAME] [--projection perspective|orthographic|fisheye|equirect]
+    ///  [--fov DEGREES] [--aperture RADIUS] [--focus DISTANCE]`
+    pub fn from_args(args: &[String], width: u32, height: u32) -> Result<Option<Self>, String> {
+        let Some(position) = args.iter().position(|arg| arg == "--sequence") else {
+            return Ok(None);
+        };
+        let output_dir = args.get(position + 1).ok_or("--sequence needs an output directory")?;
+
+        let mut settings = SequenceSettings {
+            output_dir: PathBuf::from(output_dir),
+            first_frame: 0,
+            last_frame: 119,
+            fps: 24.0,
+            width,
+            height,
+            format: FrameFormat::Png,
+            samples: 1,
+            motion_blur: false,
+            shutter: 0.5,
+            turntable: false,
+            camera_animation: false,
+            camera_path: None,
+            bookmark: None,
+            projection: None,
+            fov: None,
+            aperture: None,
+            focal_distance: None,
+        };
+
+        let value = |name: &str| args.iter().position(|arg| arg == name).map(|i| args.get(i + 1).map(String::as_str));
+        let invalid = |name: &str| format!("Invalid value for {}", name);
+
+        if let Some(frames) = value("--frames") {
+            let (first, last) = frames.and_then(|f| f.split_once('-')).ok_or_else(|| invalid("--frames"))?;
+            settings.first_frame = first.parse().map_err(|_| invalid("--frames"))?;
+            settings.last_frame = last.parse().map_err(|_| invalid("--frames"))?;
+        }
+        if let Some(fps) = value("--fps") {
+            settings.fps = fps.and_then(|f| f.parse().ok()).filter(|&f: &f32| f.is_finite() && f > 0.0).ok_or_else(|| invalid("--fps"))?;
+        }
+        if let Some(size) = value("--size") {
+            let (w, h) = size.and_then(|s| s.split_once('x')).ok_or_else(|| invalid("--size"))?;
+            settings.width = w.parse().ok().filter(|&w| w > 0).ok_or_else(|| invalid("--size"))?;
+            settings.height = h.parse().ok().filter(|&h| h > 0).ok_or_else(|| invalid("--size"))?;
+        }
+        if let Some(format) = value("--format") {
+            settings.format = match format {
+                Some("png") => FrameFormat::Png,
+                Some("exr") => FrameFormat::Exr,
+                _ => return Err(invalid("--format")),
+            };
+        }
+        if let Some(samples) = value("--samples") {
+            settings.samples = samples.and_then(|s| s.parse().ok()).filter(|&s| s > 0).ok_or_else(|| invalid("--samples"))?;
+        }
+        if let Some(shutter) = value("--shutter") {
+            settings.shutter = shutter.and_then(|s| s.parse().ok()).filter(|s| (0.0..=1.0).contains(s)).ok_or_else(|| invalid("--shutter"))?;
+        }
+        if let Some(camera_path) = value("--camera-path") {
+            settings.camera_path = Some(PathBuf::from(camera_path.ok_or_else(|| invalid("--camera-path"))?));
+        }
+        if let Some(bookmark) = value("--bookmark") {
+            settings.bookmark = Some(bookmark.ok_or_else(|| invalid("--bookmark"))?.to_string());
+        }
+        if let Some(projection) = value("--projection") {
+            settings.projection = Some(match projection {
+                Some("perspective") => Projection::Perspective,
+                Some("orthographic") => Projection::Orthographic,
+                Some("fisheye") => Projection::Fisheye { fov: std::f32::consts::PI },
+                Some("equirect") => Projection::Equirectangular,
+                _ => return Err(invalid("--projection")),
+            });
+        }
+        if let Some(fov) = value("--fov") {
+            // El mismo rango que admite la cámara, para no recortar el valor en silencio
+            let degrees: f32 = fov.and_then(|f| f.parse().ok()).filter(|&f: &f32| (MIN_FOV..=MAX_FOV).contains(&f.to_radians())).ok_or_else(|| {
+                format!("Invalid value for --fov (between {:.1} and {:.1} degrees)", MIN_FOV.to_degrees(), MAX_FOV.to_degrees())
+            })?;
+            settings.fov = Some(degrees.to_radians());
+        }
+        if let Some(aperture) = value("--aperture") {
+            settings.aperture = Some(aperture.and_then(|a| a.parse().ok()).filter(|&a: &f32| a >= 0.0).ok_or_else(|| invalid("--aperture"))?);
+        }
+        if let Some(focus) = value("--focus") {
+            settings.focal_distance = Some(focus.and_then(|f| f.parse().ok()).filter(|&f: &f32| f > 0.0).ok_or_else(|| invalid("--focus"))?);
+        }
+        settings.motion_blur = args.iter().any(|arg| arg == "--motion-blur");
+        settings.turntable = args.iter().any(|arg| arg == "--turntable");
+        settings.camera_animation = args.iter().any(|arg| arg == "--camera-animation");
+
+        if settings.first_frame > settings.last_frame {
+            return Err(invalid("--frames"));
+        }
+        Ok(Some(settings))
+    }
+
+    pub fn frame_path(&self, frame: u32) -> PathBuf {
+        self.output_dir.join(format!("frame_{:04}.{}", frame, self.format.extension()))
+    }
+
+    /// Frames of the range not yet written
+    pub fn pending_frames(&self) -> Vec<u32> {
+        (self.first_frame..=self.last_frame)
+            .filter(|&frame| !self.frame_path(frame).exists())
+            .collect()
+    }
+
+    /// Scene times at which the frame's shutter opens and closes
+    pub fn shutter_interval(&self, frame: u32) -> (f32, f32) {
+        let open = frame as f32 / self.fps;
+        if !self.motion_blur {
+            return (open, open);
+        }
+        (open, open + self.shutter / self.fps)
+    }
+
+    /// Scene rotation of the turntable at `time`: one full turn over the range
+    pub fn turntable_angle(&self, time: f32) -> f32 {
+        if !self.turntable {
+            return 0.0;
+        }
+        let duration = (self.last_frame - self.first_frame + 1) as f32 / self.fps;
+        2.0 * std::f32::consts::PI * (time - self.first_frame as f32 / self.fps) / duration
+    }
+
+    /// Writes a frame of linear radiance, row by row from the top. The file
+    /// only gets its final name once complete, so a half-written frame is
+    /// never mistaken for a finished one when resuming.
+    pub fn write_frame(&self, frame: u32, pixels: &[Vector3]) -> Result<(), String> {
+        fs::create_dir_all(&self.output_dir).map_err(|e| format!("Failed to create {}: {}", self.output_dir.display(), e))?;
+        let path = self.frame_path(frame);
+        let partial = self.output_dir.join(format!("partial_{:04}.{}", frame, self.format.extension()));
+
+        match self.format {
+            FrameFormat::Png => {
+                let mut image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+                for (index, pixel) in pixels.iter().enumerate() {
+                    let (x, y) = (index as u32 % self.width, index as u32 / self.width);
+                    image.draw_pixel(x as i32, y as i32, vector3_to_color(*pixel));
+                }
+                // raylib no informa del fallo: se borra el parcial de un intento anterior
+                // y se comprueba después que el archivo exista y no esté vacío
+                let _ = fs::remove_file(&partial);
+                image.export_image(&partial.to_string_lossy());
+                if !fs::metadata(&partial).is_ok_and(|metadata| metadata.len() > 0) {
+                    return Err(format!("Failed to write {}", partial.display()));
+                }
+            }
+            FrameFormat::Exr => {
+                let bytes = encode_exr(self.width, self.height, pixels);
+                fs::File::create(&partial)
+                    .and_then(|mut file| file.write_all(&bytes))
+                    .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+            }
+        }
+
+        fs::rename(&partial, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+// Atributo de la cabecera OpenEXR: nombre, tipo, tamaño y valor
+fn exr_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+/// Minimal scanline OpenEXR: 32-bit float B, G, R channels, no compression
+fn encode_exr(width: u32, height: u32, pixels: &[Vector3]) -> Vec<u8> {
+    let mut bytes = vec![0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]; // Número mágico y versión 2
+
+    // Canales en orden alfabético: tipo 2 = FLOAT, sin submuestreo
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"] {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&2i32.to_le_bytes());
+        channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear y reservado
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1].iter().flat_map(|v| v.to_le_bytes()).collect();
+    exr_attribute(&mut bytes, "channels", "chlist", &channels);
+    exr_attribute(&mut bytes, "compression", "compression", &[0]);
+    exr_attribute(&mut bytes, "dataWindow", "box2i", &window);
+    exr_attribute(&mut bytes, "displayWindow", "box2i", &window);
+    exr_attribute(&mut bytes, "lineOrder", "lineOrder", &[0]);
+    exr_attribute(&mut bytes, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    exr_attribute(&mut bytes, "screenWindowCenter", "v2f", &[0; 8]);
+    exr_attribute(&mut bytes, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    bytes.push(0);
+
+    // Tabla de desplazamientos: un bloque por fila
+    let row_size = 8 + width as usize * 3 * 4;
+    let table_end = bytes.len() + height as usize * 8;
+    for y in 0..height as usize {
+        bytes.extend_from_slice(&((table_end + y * row_size) as u64).to_le_bytes());
+    }
+
+    for (y, row) in pixels.chunks(width as usize).enumerate() {
+        bytes.extend_from_slice(&(y as i32).to_le_bytes());
+        bytes.extend_from_slice(&((width * 3 * 4) as i32).to_le_bytes());
+        for channel in [|p: &Vector3| p.z, |p: &Vector3| p.y, |p: &Vector3| p.x] {
+            for pixel in row {
+                bytes.extend_from_slice(&channel(pixel).to_le_bytes());
+            }
+        }
+    }
+    bytes
+}
@@ -0,0 +1,403 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::TAU;
+use std::fs;
+use std::path::Path;
+use crate::animation::Track;
+use crate::procedural_texture::{NoisePattern, ProceduralTexture};
+use crate::textures::TextureManager;
+
+// Paso de las diferencias finitas del nodo de relieve
+const BUMP_EPSILON: f32 = 1e-3;
+
+/// Everything a node can read about the surface being shaded
+pub struct ShadingContext<'a> {
+    pub point: Vector3,
+    pub object_point: Vector3,
+    pub normal: Vector3,
+    pub view_dir: Vector3, // Desde la superficie hacia la cámara
+    pub u: f32,
+    pub v: f32,
+    pub base_color: Vector3, // Color de la textura de imagen o difuso del material
+    pub time: f32,           // Segundos de animación de la escena
+    pub texture_manager: &'a TextureManager,
+}
+
+/// Node of a material shading graph. Every node evaluates to a `Vector3`;
+/// scalar nodes return the same value in all three components, so scalars
+/// and colours can be mixed freely.
+#[derive(Clone)]
+pub enum ShaderNode {
+    // Entradas constantes
+    Constant(f32),
+    Color(Vector3),
+    Texture(String),               // Imagen muestreada en las UV del impacto
+    Noise(ProceduralTexture),      // Valor del patrón en [0, 1]
+    NoiseColor(ProceduralTexture), // Rampa de color del patrón
+    Keyframes(Track<Vector3>),     // Valor animado según el tiempo de la escena
+    // Entradas geométricas
+    BaseColor,
+    Normal,
+    Height, // Altura del punto en el mundo
+    Facing, // |n · v|: 1 de frente, 0 de canto (útil para efectos Fresnel)
+    Time,
+    // Operaciones
+    Component(Box<ShaderNode>, usize), // Eje 0, 1 o 2 de un vector, como escalar
+    Add(Box<ShaderNode>, Box<ShaderNode>),
+    Multiply(Box<ShaderNode>, Box<ShaderNode>),
+    Mix(Box<ShaderNode>, Box<ShaderNode>, Box<ShaderNode>), // a -> b según t
+    Smoothstep(f32, f32, Box<ShaderNode>),
+    Sine(Box<ShaderNode>),
+    Bump(Box<ShaderNode>, f32), // Normal inclinada según una altura escalar
+}
+
+impl ShaderNode {
+    pub fn constant(value: f32) -> Self {
+        ShaderNode::Constant(value)
+    }
+
+    pub fn color(color: Vector3) -> Self {
+        ShaderNode::Color(color)
+    }
+
+    pub fn texture(path: &str) -> Self {
+        ShaderNode::Texture(path.to_string())
+    }
+
+    pub fn noise(texture: ProceduralTexture) -> Self {
+        ShaderNode::Noise(texture)
+    }
+
+    pub fn noise_color(texture: ProceduralTexture) -> Self {
+        ShaderNode::NoiseColor(texture)
+    }
+
+    pub fn keyframes(track: Track<Vector3>) -> Self {
+        ShaderNode::Keyframes(track)
+    }
+
+    /// Normal's Y component: 1 on faces pointing up, -1 facing down
+    pub fn upness() -> Self {
+        ShaderNode::component(ShaderNode::Normal, 1)
+    }
+
+    pub fn component(node: ShaderNode, axis: usize) -> Self {
+        ShaderNode::Component(Box::new(node), axis)
+    }
+
+    pub fn add(a: ShaderNode, b: ShaderNode) -> Self {
+        ShaderNode::Add(Box::new(a), Box::new(b))
+    }
+
+    pub fn multiply(a: ShaderNode, b: ShaderNode) -> Self {
+        ShaderNode::Multiply(Box::new(a), Box::new(b))
+    }
+
+    pub fn mix(a: ShaderNode, b: ShaderNode, t: ShaderNode) -> Self {
+        ShaderNode::Mix(Box::new(a), Box::new(b), Box::new(t))
+    }
+
+    /// 0 below `edge0`, 1 above `edge1`, smooth in between (edges may be reversed)
+    pub fn smoothstep(edge0: f32, edge1: f32, x: ShaderNode) -> Self {
+        ShaderNode::Smoothstep(edge0, edge1, Box::new(x))
+    }
+
+    pub fn sine(x: ShaderNode) -> Self {
+        ShaderNode::Sine(Box::new(x))
+    }
+
+    /// Oscillates between 0 and 1 `frequency` times per second
+    pub fn pulse(frequency: f32) -> Self {
+        let wave = ShaderNode::sine(ShaderNode::multiply(ShaderNode::Time, ShaderNode::constant(TAU * frequency)));
+        ShaderNode::add(ShaderNode::multiply(wave, ShaderNode::constant(0.5)), ShaderNode::constant(0.5))
+    }
+
+    /// Normal perturbed as if the surface were displaced along it by
+    /// `height * strength`
+    pub fn bump(height: ShaderNode, strength: f32) -> Self {
+        ShaderNode::Bump(Box::new(height), strength)
+    }
+
+    /// Whether the node's value changes with scene time
+    pub fn is_animated(&self) -> bool {
+        match self {
+            ShaderNode::Keyframes(_) | ShaderNode::Time => true,
+            ShaderNode::Noise(texture) | ShaderNode::NoiseColor(texture) => texture.velocity != Vector3::zero(),
+            ShaderNode::Constant(_) | ShaderNode::Color(_) | ShaderNode::Texture(_) => false,
+            ShaderNode::BaseColor | ShaderNode::Normal | ShaderNode::Height | ShaderNode::Facing => false,
+            ShaderNode::Component(node, _) | ShaderNode::Smoothstep(_, _, node) | ShaderNode::Sine(node) | ShaderNode::Bump(node, _) => node.is_animated(),
+            ShaderNode::Add(a, b) | ShaderNode::Multiply(a, b) => a.is_animated() || b.is_animated(),
+            ShaderNode::Mix(a, b, t) => a.is_animated() || b.is_animated() || t.is_animated(),
+        }
+    }
+
+    pub fn evaluate(&self, context: &ShadingContext) -> Vector3 {
+        let splat = |value: f32| Vector3::new(value, value, value);
+        match self {
+            ShaderNode::Constant(value) => splat(*value),
+            ShaderNode::Color(color) => *color,
+            ShaderNode::Texture(path) => context.texture_manager.sample_color(path, context.u, context.v),
+            ShaderNode::Noise(texture) => splat(texture.value(&context.point, &context.object_point, context.time)),
+            ShaderNode::NoiseColor(texture) => texture.color(&context.point, &context.object_point, context.time),
+            ShaderNode::Keyframes(track) => track.sample(context.time).unwrap_or(Vector3::zero()),
+            ShaderNode::BaseColor => context.base_color,
+            ShaderNode::Normal => context.normal,
+            ShaderNode::Height => splat(context.point.y),
+            ShaderNode::Facing => splat(context.normal.dot(context.view_dir).abs()),
+            ShaderNode::Time => splat(context.time),
+            ShaderNode::Component(node, axis) => {
+                let value = node.evaluate(context);
+                splat(match axis {
+                    0 => value.x,
+                    1 => value.y,
+                    _ => value.z,
+                })
+            }
+            ShaderNode::Add(a, b) => a.evaluate(context) + b.evaluate(context),
+            ShaderNode::Multiply(a, b) => a.evaluate(context) * b.evaluate(context),
+            ShaderNode::Mix(a, b, t) => {
+                let (a, b) = (a.evaluate(context), b.evaluate(context));
+                let t = t.evaluate(context).x.clamp(0.0, 1.0);
+                a + (b - a) * t
+            }
+            ShaderNode::Smoothstep(edge0, edge1, x) => {
+                let t = ((x.evaluate(context).x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+                splat(t * t * (3.0 - 2.0 * t))
+            }
+            ShaderNode::Sine(x) => {
+                let value = x.evaluate(context);
+                Vector3::new(value.x.sin(), value.y.sin(), value.z.sin())
+            }
+            ShaderNode::Bump(height, strength) => {
+                // Gradiente de la altura desplazando el punto en los tres ejes
+                let center = height.evaluate(context).x;
+                let sample = |offset: Vector3| {
+                    let shifted = ShadingContext {
+                        point: context.point + offset,
+                        object_point: context.object_point + offset,
+                        ..*context
+                    };
+                    (height.evaluate(&shifted).x - center) / BUMP_EPSILON
+                };
+                let gradient = Vector3::new(
+                    sample(Vector3::new(BUMP_EPSILON, 0.0, 0.0)),
+                    sample(Vector3::new(0.0, BUMP_EPSILON, 0.0)),
+                    sample(Vector3::new(0.0, 0.0, BUMP_EPSILON)),
+                );
+
+                // Solo cuenta la parte del gradiente tangente a la superficie
+                let normal = context.normal;
+                let tangential = gradient - normal * gradient.dot(normal);
+                (normal - tangential * *strength).normalized()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MaterialInput {
+    Color,
+    Specular,     // Exponente de Phong
+    Roughness,    // 0 = brillo nítido, 1 = muy disperso
+    Reflectivity,
+    Transparency,
+    Normal,       // Dirección de la normal (p. ej. un nodo Bump)
+    Emission,     // Luz propia, sumada sin sombras ni atenuación por la luz
+}
+
+/// Nodes wired into a material's inputs; unconnected inputs keep the
+/// material's constant values
+#[derive(Clone, Default)]
+pub struct MaterialGraph {
+    pub color: Option<ShaderNode>,
+    pub specular: Option<ShaderNode>,
+    pub roughness: Option<ShaderNode>,
+    pub reflectivity: Option<ShaderNode>,
+    pub transparency: Option<ShaderNode>,
+    pub normal: Option<ShaderNode>,
+    pub emission: Option<ShaderNode>,
+}
+
+impl MaterialInput {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "color" => Some(MaterialInput::Color),
+            "specular" => Some(MaterialInput::Specular),
+            "roughness" => Some(MaterialInput::Roughness),
+            "reflectivity" => Some(MaterialInput::Reflectivity),
+            "transparency" => Some(MaterialInput::Transparency),
+            "normal" => Some(MaterialInput::Normal),
+            "emission" => Some(MaterialInput::Emission),
+            _ => None,
+        }
+    }
+}
+
+impl MaterialGraph {
+    pub fn input(&self, input: MaterialInput) -> Option<&ShaderNode> {
+        match input {
+            MaterialInput::Color => self.color.as_ref(),
+            MaterialInput::Specular => self.specular.as_ref(),
+            MaterialInput::Roughness => self.roughness.as_ref(),
+            MaterialInput::Reflectivity => self.reflectivity.as_ref(),
+            MaterialInput::Transparency => self.transparency.as_ref(),
+            MaterialInput::Normal => self.normal.as_ref(),
+            MaterialInput::Emission => self.emission.as_ref(),
+        }
+    }
+
+    pub fn is_animated(&self) -> bool {
+        [&self.color, &self.specular, &self.roughness, &self.reflectivity, &self.transparency, &self.normal, &self.emission]
+            .into_iter()
+            .flatten()
+            .any(ShaderNode::is_animated)
+    }
+
+    pub fn connect(&mut self, input: MaterialInput, node: ShaderNode) {
+        let slot = match input {
+            MaterialInput::Color => &mut self.color,
+            MaterialInput::Specular => &mut self.specular,
+            MaterialInput::Roughness => &mut self.roughness,
+            MaterialInput::Reflectivity => &mut self.reflectivity,
+            MaterialInput::Transparency => &mut self.transparency,
+            MaterialInput::Normal => &mut self.normal,
+            MaterialInput::Emission => &mut self.emission,
+        };
+        *slot = Some(node);
+    }
+}
+
+/// Node wired into an input of a named material, read from a graph file
+pub struct GraphConnection {
+    pub material: String,
+    pub input: MaterialInput,
+    pub node: ShaderNode,
+}
+
+/// Reads the node graphs written as text at `path`; a missing file just
+/// means none. Each line is `MATERIAL INPUT NODE`, where a node is a number,
+/// one of `base normal height facing time upness`, or a parenthesised call:
+/// `(color R G B)`, `(texture PATH)`, `(noise PATTERN SCALE OPTIONS...)`,
+/// `(noise_color PATTERN SCALE OPTIONS...)`, `(component NODE AXIS)`,
+/// `(add A B)`, `(multiply A B)`, `(mix A B T)`, `(smoothstep E0 E1 NODE)`,
+/// `(sine NODE)`, `(pulse FREQUENCY)` or `(bump NODE STRENGTH)`. Noise options
+/// are `object`, `seed=N`, `octaves=N`, `warp=W`, `velocity=X,Y,Z`,
+/// `low=R,G,B` and `high=R,G,B`. `#` starts a comment line.
+pub fn load(path: &Path) -> Result<Vec<GraphConnection>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(number, line)| parse_connection(line).map_err(|e| format!("{}:{}: {}", path.display(), number + 1, e)))
+        .collect()
+}
+
+fn parse_connection(line: &str) -> Result<GraphConnection, String> {
+    let spaced = line.replace('(', " ( ").replace(')', " ) ");
+    let mut parser = NodeParser { tokens: spaced.split_whitespace().collect(), position: 0 };
+    let material = parser.next()?.to_string();
+    let input = parser.next()?;
+    let input = MaterialInput::from_name(input).ok_or_else(|| format!("Unknown material input '{}'", input))?;
+    let node = parser.node()?;
+    if let Some(extra) = parser.tokens.get(parser.position) {
+        return Err(format!("Unexpected '{}' after the node", extra));
+    }
+    Ok(GraphConnection { material, input, node })
+}
+
+// Lectura recursiva de nodos en notación prefija, token a token
+struct NodeParser<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> NodeParser<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        let token = self.tokens.get(self.position).ok_or("Unexpected end of line")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn number(&mut self) -> Result<f32, String> {
+        let token = self.next()?;
+        token.parse().map_err(|_| format!("Invalid number '{}'", token))
+    }
+
+    fn node(&mut self) -> Result<ShaderNode, String> {
+        let token = self.next()?;
+        if token != "(" {
+            return match token {
+                "base" => Ok(ShaderNode::BaseColor),
+                "normal" => Ok(ShaderNode::Normal),
+                "height" => Ok(ShaderNode::Height),
+                "facing" => Ok(ShaderNode::Facing),
+                "time" => Ok(ShaderNode::Time),
+                "upness" => Ok(ShaderNode::upness()),
+                _ => token.parse().map(ShaderNode::constant).map_err(|_| format!("Unknown node '{}'", token)),
+            };
+        }
+
+        let name = self.next()?;
+        let node = match name {
+            "color" => ShaderNode::color(Vector3::new(self.number()?, self.number()?, self.number()?)),
+            "texture" => ShaderNode::texture(self.next()?),
+            "noise" => ShaderNode::noise(self.texture()?),
+            "noise_color" => ShaderNode::noise_color(self.texture()?),
+            "component" => {
+                let node = self.node()?;
+                match self.next()? {
+                    axis @ ("0" | "1" | "2") => ShaderNode::component(node, axis.parse().unwrap_or_default()),
+                    axis => return Err(format!("Invalid axis '{}'", axis)),
+                }
+            }
+            "add" => ShaderNode::add(self.node()?, self.node()?),
+            "multiply" => ShaderNode::multiply(self.node()?, self.node()?),
+            "mix" => ShaderNode::mix(self.node()?, self.node()?, self.node()?),
+            "smoothstep" => ShaderNode::smoothstep(self.number()?, self.number()?, self.node()?),
+            "sine" => ShaderNode::sine(self.node()?),
+            "pulse" => ShaderNode::pulse(self.number()?),
+            "bump" => ShaderNode::bump(self.node()?, self.number()?),
+            _ => return Err(format!("Unknown node '{}'", name)),
+        };
+        match self.next()? {
+            ")" => Ok(node),
+            token => Err(format!("Expected ')' after '{}', found '{}'", name, token)),
+        }
+    }
+
+    // Patrón, escala y opciones `clave=valor` hasta el paréntesis de cierre
+    fn texture(&mut self) -> Result<ProceduralTexture, String> {
+        let pattern = match self.next()? {
+            "perlin" => NoisePattern::Perlin,
+            "simplex" => NoisePattern::Simplex,
+            "worley" => NoisePattern::Worley,
+            "worley_edges" => NoisePattern::WorleyEdges,
+            "fbm" => NoisePattern::Fbm,
+            "turbulence" => NoisePattern::Turbulence,
+            pattern => return Err(format!("Unknown noise pattern '{}'", pattern)),
+        };
+        let mut texture = ProceduralTexture::new(pattern, self.number()?);
+
+        while let Some(&option) = self.tokens.get(self.position).filter(|&&token| token != ")") {
+            self.position += 1;
+            let invalid = || format!("Invalid noise option '{}'", option);
+            let vector = |value: &str| match value.split(',').map(str::parse).collect::<Result<Vec<f32>, _>>().as_deref() {
+                Ok(&[x, y, z]) => Ok(Vector3::new(x, y, z)),
+                _ => Err(invalid()),
+            };
+            texture = match option.split_once('=') {
+                None if option == "object" => texture.in_object_space(),
+                Some(("seed", value)) => texture.with_seed(value.parse().map_err(|_| invalid())?),
+                Some(("octaves", value)) => texture.with_octaves(value.parse().map_err(|_| invalid())?),
+                Some(("warp", value)) => texture.with_warp(value.parse().map_err(|_| invalid())?),
+                Some(("velocity", value)) => texture.with_velocity(vector(value)?),
+                Some(("low", value)) => texture.with_colors(vector(value)?, texture.color_high),
+                Some(("high", value)) => texture.with_colors(texture.color_low, vector(value)?),
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(texture)
+    }
+}
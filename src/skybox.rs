@@ -1,4 +1,5 @@
 use raylib::prelude::*;
+use crate::noise::fbm;
 
 #[derive(Clone)]
 pub enum SkyboxType {
@@ -131,9 +132,9 @@ impl Skybox {
         let mut final_color = sky_color * (0.7 + height_factor * 0.3);
 
         // Generar nubes usando múltiples octavas de ruido
-        let cloud_noise1 = self.procedural_noise(*ray_direction * 5.0);
-        let cloud_noise2 = self.procedural_noise(*ray_direction * 12.0) * 0.5;
-        let cloud_noise3 = self.procedural_noise(*ray_direction * 25.0) * 0.25;
+        let cloud_noise1 = self.continuous_noise(*ray_direction * 5.0);
+        let cloud_noise2 = self.continuous_noise(*ray_direction * 12.0) * 0.5;
+        let cloud_noise3 = self.continuous_noise(*ray_direction * 25.0) * 0.25;
         
         let cloud_factor = (cloud_noise1 + cloud_noise2 + cloud_noise3) / 1.75;
         
@@ -160,8 +161,8 @@ impl Skybox {
         let mut final_color = space_color;
 
         // Nebulosas usando ruido fractal
-        let nebula_noise1 = self.procedural_noise(*ray_direction * 3.0);
-        let nebula_noise2 = self.procedural_noise(*ray_direction * 7.0) * 0.5;
+        let nebula_noise1 = self.continuous_noise(*ray_direction * 3.0);
+        let nebula_noise2 = self.continuous_noise(*ray_direction * 7.0) * 0.5;
         let combined_nebula = nebula_noise1 + nebula_noise2;
 
         if combined_nebula > 0.3 {
@@ -186,7 +187,12 @@ impl Skybox {
         final_color
     }
 
-    // Función de ruido procedural simple (basada en hash)
+    // Ruido fractal continuo en [0, 1] para nubes y nebulosas
+    fn continuous_noise(&self, p: Vector3) -> f32 {
+        (fbm(p, 4, 0) + 0.5).clamp(0.0, 1.0)
+    }
+
+    // Valor aleatorio por punto (basado en hash): sin continuidad, ideal para estrellas
     fn procedural_noise(&self, p: Vector3) -> f32 {
         let mut hash = ((p.x * 73856093.0) as i32) ^ ((p.y * 19349663.0) as i32) ^ ((p.z * 83492791.0) as i32);
         hash = (hash ^ (hash >> 13)) * 1274126177;
@@ -0,0 +1,287 @@
+use raylib::prelude::Vector3;
+use std::fs;
+use std::path::Path;
+use crate::camera::Camera;
+
+// Muestras por tramo de la tabla de longitud de arco
+const SAMPLES_PER_SEGMENT: usize = 32;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SplineKind {
+    /// Passes through every point, with tangents from the neighbouring points
+    CatmullRom,
+    /// Cubic segments sharing end points: anchor, two handles, anchor, two
+    /// handles... Points past the last complete segment are ignored.
+    Bezier,
+}
+
+/// Curve through control points, traversed at constant speed: positions are
+/// looked up by fraction of the arc length, not by curve parameter, so the
+/// camera neither rushes through sparse points nor crawls between dense ones
+#[derive(Clone)]
+pub struct Spline {
+    kind: SplineKind,
+    points: Vec<Vector3>,
+    closed: bool,      // Catmull-Rom: el último punto vuelve a enlazar con el primero
+    lengths: Vec<f32>, // Longitud acumulada en cada muestra de la tabla
+}
+
+impl Spline {
+    pub fn new(kind: SplineKind) -> Self {
+        Spline {
+            kind,
+            points: Vec::new(),
+            closed: false,
+            lengths: vec![0.0],
+        }
+    }
+
+    pub fn with_points(mut self, points: Vec<Vector3>) -> Self {
+        self.points = points;
+        self.rebuild();
+        self
+    }
+
+    pub fn closed(mut self) -> Self {
+        self.closed = true;
+        self.rebuild();
+        self
+    }
+
+    pub fn kind(&self) -> SplineKind {
+        self.kind
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn points(&self) -> &[Vector3] {
+        &self.points
+    }
+
+    pub fn set_kind(&mut self, kind: SplineKind) {
+        self.kind = kind;
+        self.rebuild();
+    }
+
+    pub fn push_point(&mut self, point: Vector3) {
+        self.points.push(point);
+        self.rebuild();
+    }
+
+    pub fn set_point(&mut self, index: usize, point: Vector3) {
+        self.points[index] = point;
+        self.rebuild();
+    }
+
+    pub fn remove_point(&mut self, index: usize) {
+        self.points.remove(index);
+        self.rebuild();
+    }
+
+    pub fn length(&self) -> f32 {
+        self.lengths.last().copied().unwrap_or(0.0)
+    }
+
+    /// Curve parameter reached at fraction `u` in [0, 1] of the arc length:
+    /// the integer part is the segment, the fractional part the position in it
+    pub fn parameter_at(&self, u: f32) -> f32 {
+        if self.segments() == 0 {
+            return 0.0;
+        }
+        let target = u.clamp(0.0, 1.0) * self.length();
+
+        // Muestra de la tabla donde se alcanza la distancia, interpolando dentro de ella
+        let index = self.lengths.partition_point(|&length| length < target).clamp(1, self.lengths.len() - 1);
+        let (before, after) = (self.lengths[index - 1], self.lengths[index]);
+        let fraction = if after > before { (target - before) / (after - before) } else { 0.0 };
+        (index as f32 - 1.0 + fraction) / SAMPLES_PER_SEGMENT as f32
+    }
+
+    /// Point at curve `parameter`, as returned by `parameter_at`. A single
+    /// point is returned as is; `None` without points.
+    pub fn point_at(&self, parameter: f32) -> Option<Vector3> {
+        if self.segments() == 0 {
+            return self.points.first().copied();
+        }
+        Some(self.evaluate(parameter.clamp(0.0, self.segments() as f32)))
+    }
+
+    fn segments(&self) -> usize {
+        let count = self.points.len();
+        match self.kind {
+            SplineKind::CatmullRom if count < 2 => 0,
+            SplineKind::CatmullRom if self.closed => count,
+            SplineKind::CatmullRom => count - 1,
+            SplineKind::Bezier => count.saturating_sub(1) / 3,
+        }
+    }
+
+    // Punto de control con los extremos repetidos (o enlazados si es cerrada)
+    fn control(&self, index: isize) -> Vector3 {
+        let count = self.points.len() as isize;
+        let index = if self.closed { index.rem_euclid(count) } else { index.clamp(0, count - 1) };
+        self.points[index as usize]
+    }
+
+    // Posición por parámetro de la curva: el tramo es la parte entera
+    fn evaluate(&self, parameter: f32) -> Vector3 {
+        let segment = (parameter.floor() as usize).min(self.segments() - 1);
+        let t = parameter - segment as f32;
+        match self.kind {
+            SplineKind::CatmullRom => {
+                let i = segment as isize;
+                let (p0, p1, p2, p3) = (self.control(i - 1), self.control(i), self.control(i + 1), self.control(i + 2));
+                (p1 * 2.0
+                    + (p2 - p0) * t
+                    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (t * t)
+                    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * (t * t * t))
+                    * 0.5
+            }
+            SplineKind::Bezier => {
+                let p = &self.points[segment * 3..segment * 3 + 4];
+                let inv = 1.0 - t;
+                p[0] * (inv * inv * inv) + p[1] * (3.0 * inv * inv * t) + p[2] * (3.0 * inv * t * t) + p[3] * (t * t * t)
+            }
+        }
+    }
+
+    fn rebuild(&mut self) {
+        self.lengths = vec![0.0];
+        let samples = self.segments() * SAMPLES_PER_SEGMENT;
+        if samples == 0 {
+            return;
+        }
+        let mut previous = self.evaluate(0.0);
+        for sample in 1..=samples {
+            let point = self.evaluate(sample as f32 / SAMPLES_PER_SEGMENT as f32);
+            self.lengths.push(self.length() + (point - previous).length());
+            previous = point;
+        }
+    }
+}
+
+/// Cinematic camera path: one spline for the eye and one for the point
+/// looked at. The eye moves at constant speed over `duration` seconds and the
+/// target follows the same curve parameter, so both reach each control point
+/// together
+#[derive(Clone)]
+pub struct CameraPath {
+    pub eye: Spline,
+    pub target: Spline,
+    pub duration: f32,
+}
+
+impl CameraPath {
+    pub fn new(kind: SplineKind, duration: f32) -> Self {
+        CameraPath {
+            eye: Spline::new(kind),
+            target: Spline::new(kind),
+            duration,
+        }
+    }
+
+    /// Loops back from the last control point to the first (Catmull-Rom)
+    pub fn closed(mut self) -> Self {
+        self.eye = self.eye.closed();
+        self.target = self.target.closed();
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.eye.points().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn set_kind(&mut self, kind: SplineKind) {
+        self.eye.set_kind(kind);
+        self.target.set_kind(kind);
+    }
+
+    /// Adds the camera's current eye and center as the last control point
+    pub fn push_view(&mut self, camera: &Camera) {
+        self.eye.push_point(camera.eye);
+        self.target.push_point(camera.center);
+    }
+
+    pub fn set_view(&mut self, index: usize, camera: &Camera) {
+        self.eye.set_point(index, camera.eye);
+        self.target.set_point(index, camera.center);
+    }
+
+    pub fn remove_view(&mut self, index: usize) {
+        self.eye.remove_point(index);
+        self.target.remove_point(index);
+    }
+
+    /// Places the camera on the control point `index`
+    pub fn show_view(&self, index: usize, camera: &mut Camera) {
+        camera.eye = self.eye.points()[index];
+        camera.center = self.target.points()[index];
+        camera.update_basis_vectors();
+    }
+
+    /// Places the camera on the path at `time`, looping every `duration` seconds
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        let u = (time / self.duration).rem_euclid(1.0);
+        let parameter = self.eye.parameter_at(u);
+        if let (Some(eye), Some(center)) = (self.eye.point_at(parameter), self.target.point_at(parameter)) {
+            camera.eye = eye;
+            camera.center = center;
+            camera.update_basis_vectors();
+        }
+    }
+
+    /// Reads a path file: `kind catmull-rom|bezier`, `duration SECONDS`,
+    /// optional `closed`, then one `point EX EY EZ TX TY TZ` line per control
+    /// point (eye, then target). Lines starting with `#` are comments.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut camera_path = CameraPath::new(SplineKind::CatmullRom, 10.0);
+        let (mut eyes, mut targets, mut closed) = (Vec::new(), Vec::new(), false);
+
+        for (number, line) in text.lines().enumerate() {
+            let invalid = || format!("{}:{}: Invalid line '{}'", path.display(), number + 1, line);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["kind", "catmull-rom"] => camera_path.set_kind(SplineKind::CatmullRom),
+                ["kind", "bezier"] => camera_path.set_kind(SplineKind::Bezier),
+                ["duration", seconds] => {
+                    camera_path.duration = seconds.parse().ok().filter(|&d: &f32| d > 0.0).ok_or_else(invalid)?;
+                }
+                ["closed"] => closed = true,
+                ["point", ..] if fields.len() == 7 => {
+                    let values: Vec<f32> = fields[1..].iter().map(|v| v.parse().map_err(|_| invalid())).collect::<Result<_, _>>()?;
+                    eyes.push(Vector3::new(values[0], values[1], values[2]));
+                    targets.push(Vector3::new(values[3], values[4], values[5]));
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        camera_path.eye = camera_path.eye.with_points(eyes);
+        camera_path.target = camera_path.target.with_points(targets);
+        Ok(if closed { camera_path.closed() } else { camera_path })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let kind = match self.eye.kind() {
+            SplineKind::CatmullRom => "catmull-rom",
+            SplineKind::Bezier => "bezier",
+        };
+        let mut text = format!("# Recorrido de cámara: punto ojo(x y z) objetivo(x y z)\nkind {}\nduration {}\n", kind, self.duration);
+        if self.eye.is_closed() {
+            text.push_str("closed\n");
+        }
+        for (eye, target) in self.eye.points().iter().zip(self.target.points()) {
+            text.push_str(&format!("point {} {} {} {} {} {}\n", eye.x, eye.y, eye.z, target.x, target.y, target.z));
+        }
+        fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
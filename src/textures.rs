@@ -65,6 +65,13 @@ impl TextureManager {
         self.textures.insert(path.to_string(), texture);
     }
 
+    /// Paths of every loaded texture, sorted
+    pub fn texture_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.cpu_textures.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
     pub fn get_pixel_color(
         &self,
         path: &str,
@@ -90,6 +97,37 @@ impl TextureManager {
         }
     }
 
+    /// Color at texture coordinates (u, v) in [0, 1]
+    pub fn sample_color(
+        &self,
+        path: &str,
+        u: f32,
+        v: f32,
+    ) -> Vector3 {
+        match self.cpu_textures.get(path) {
+            Some(cpu_texture) => {
+                let tx = (u * cpu_texture.width as f32) as u32;
+                let ty = (v * cpu_texture.height as f32) as u32;
+                self.get_pixel_color(path, tx, ty)
+            }
+            None => Vector3::one(),
+        }
+    }
+
+    /// Luminance of every pixel in [0, 1], row by row, with the image size
+    pub fn get_grayscale(
+        &self,
+        path: &str,
+    ) -> Option<(usize, usize, Vec<f32>)> {
+        let cpu_texture = self.cpu_textures.get(path)?;
+        let values = cpu_texture
+            .pixels
+            .iter()
+            .map(|c| c.x * 0.299 + c.y * 0.587 + c.z * 0.114)
+            .collect();
+        Some((cpu_texture.width as usize, cpu_texture.height as usize, values))
+    }
+
     pub fn get_texture(
         &self,
         path: &str,
@@ -0,0 +1,228 @@
+use raylib::prelude::Vector3;
+use crate::ray_intersect::{Intersect, Interval, RayIntersect};
+
+#[derive(Clone, Copy)]
+pub struct Matrix3 {
+    data: [[f32; 3]; 3],
+}
+
+impl Matrix3 {
+    pub fn identity() -> Self {
+        Matrix3 {
+            data: [
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0],
+            ]
+        }
+    }
+
+    pub fn rotation_x(angle: f32) -> Self {
+        let cos_a = angle.cos();
+        let sin_a = angle.sin();
+        Matrix3 {
+            data: [
+                [1.0, 0.0, 0.0],
+                [0.0, cos_a, -sin_a],
+                [0.0, sin_a, cos_a],
+            ]
+        }
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        let cos_a = angle.cos();
+        let sin_a = angle.sin();
+        Matrix3 {
+            data: [
+                [cos_a, 0.0, sin_a],
+                [0.0, 1.0, 0.0],
+                [-sin_a, 0.0, cos_a],
+            ]
+        }
+    }
+
+    pub fn rotation_z(angle: f32) -> Self {
+        let cos_a = angle.cos();
+        let sin_a = angle.sin();
+        Matrix3 {
+            data: [
+                [cos_a, -sin_a, 0.0],
+                [sin_a, cos_a, 0.0],
+                [0.0, 0.0, 1.0],
+            ]
+        }
+    }
+
+    /// Rotation from Euler angles in radians, applied X first, then Y, then Z
+    pub fn from_euler(angles: Vector3) -> Self {
+        Matrix3::rotation_z(angles.z)
+            .multiply(&Matrix3::rotation_y(angles.y))
+            .multiply(&Matrix3::rotation_x(angles.x))
+    }
+
+    pub fn multiply(&self, other: &Matrix3) -> Self {
+        let mut data = [[0.0; 3]; 3];
+        for (i, row) in data.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.data[i][k] * other.data[k][j]).sum();
+            }
+        }
+        Matrix3 { data }
+    }
+
+    pub fn transform_vector(&self, v: Vector3) -> Vector3 {
+        Vector3::new(
+            self.data[0][0] * v.x + self.data[0][1] * v.y + self.data[0][2] * v.z,
+            self.data[1][0] * v.x + self.data[1][1] * v.y + self.data[1][2] * v.z,
+            self.data[2][0] * v.x + self.data[2][1] * v.y + self.data[2][2] * v.z,
+        )
+    }
+
+    // La inversa de una rotación es su transpuesta
+    pub fn transpose(&self) -> Self {
+        let mut data = [[0.0; 3]; 3];
+        for (i, row) in data.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.data[j][i];
+            }
+        }
+        Matrix3 { data }
+    }
+}
+
+/// Rotation, uniform scale and translation: `p -> rotation * (p * scale) + translation`.
+/// The scale is kept uniform so hit normals only need rotating.
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub rotation: Matrix3,
+    pub scale: f32,
+    pub translation: Vector3,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform {
+            rotation: Matrix3::identity(),
+            scale: 1.0,
+            translation: Vector3::zero(),
+        }
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        Transform {
+            rotation: Matrix3::rotation_y(angle),
+            ..Transform::identity()
+        }
+    }
+
+    /// Rotates and scales around `pivot`, then moves by `translation`
+    pub fn around_pivot(pivot: Vector3, translation: Vector3, rotation: Vector3, scale: f32) -> Self {
+        let rotation = Matrix3::from_euler(rotation);
+        Transform {
+            rotation,
+            scale,
+            translation: pivot + translation - rotation.transform_vector(pivot * scale),
+        }
+    }
+
+    /// This transform followed by `outer`
+    pub fn then(&self, outer: &Transform) -> Self {
+        Transform {
+            rotation: outer.rotation.multiply(&self.rotation),
+            scale: self.scale * outer.scale,
+            translation: outer.apply_point(self.translation),
+        }
+    }
+
+    pub fn apply_point(&self, point: Vector3) -> Vector3 {
+        self.rotation.transform_vector(point * self.scale) + self.translation
+    }
+}
+
+// Objeto de la escena visto a través de una transformación: el rayo se lleva al
+// espacio local, se intersecta y el impacto se devuelve al espacio del mundo.
+// Funciona igual para cualquier primitiva (cubos, planos, discos...).
+pub struct TransformedObject<'a> {
+    object: &'a dyn RayIntersect,
+    transform: Transform,
+    inverse_rotation: Matrix3,
+}
+
+impl<'a> TransformedObject<'a> {
+    pub fn new(object: &'a dyn RayIntersect, transform: Transform) -> Self {
+        TransformedObject {
+            object,
+            transform,
+            inverse_rotation: transform.rotation.transpose(),
+        }
+    }
+
+    // Rayo en el espacio local; la dirección sigue siendo unitaria
+    fn local_ray(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> (Vector3, Vector3) {
+        let origin = self.inverse_rotation.transform_vector(*ray_origin - self.transform.translation) / self.transform.scale;
+        let direction = self.inverse_rotation.transform_vector(*ray_direction);
+        (origin, direction)
+    }
+
+    fn to_world(&self, intersect: &mut Intersect) {
+        // Con escala uniforme las distancias locales solo se multiplican por ella
+        intersect.distance *= self.transform.scale;
+        intersect.point = self.transform.apply_point(intersect.point);
+        intersect.normal = self.transform.rotation.transform_vector(intersect.normal);
+        intersect.tangent = intersect.tangent.map(|t| self.transform.rotation.transform_vector(t));
+    }
+}
+
+impl RayIntersect for TransformedObject<'_> {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let (local_origin, local_direction) = self.local_ray(ray_origin, ray_direction);
+
+        let mut intersect = self.object.ray_intersect(&local_origin, &local_direction);
+        if intersect.is_intersecting {
+            self.to_world(&mut intersect);
+        }
+        intersect
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<Interval> {
+        let (local_origin, local_direction) = self.local_ray(ray_origin, ray_direction);
+
+        let mut intervals = self.object.ray_intervals(&local_origin, &local_direction);
+        for interval in &mut intervals {
+            for boundary in [&mut interval.enter, &mut interval.exit] {
+                if boundary.is_intersecting {
+                    self.to_world(boundary);
+                }
+            }
+        }
+        intervals
+    }
+}
+
+/// Object that moves while the shutter is open: each ray sees it where it was
+/// at the ray's own time, which blurs it along its motion
+pub struct MovingObject<'a, F: Fn(f32) -> Transform> {
+    object: &'a dyn RayIntersect,
+    transform_at: F, // Transformación en cada instante del obturador, de 0 (abre) a 1 (cierra)
+}
+
+impl<'a, F: Fn(f32) -> Transform> MovingObject<'a, F> {
+    pub fn new(object: &'a dyn RayIntersect, transform_at: F) -> Self {
+        MovingObject { object, transform_at }
+    }
+}
+
+impl<F: Fn(f32) -> Transform> RayIntersect for MovingObject<'_, F> {
+    // Sin instante explícito se usa la apertura del obturador
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vector3, ray_direction: &Vector3, time: f32) -> Intersect {
+        TransformedObject::new(self.object, (self.transform_at)(time)).ray_intersect(ray_origin, ray_direction)
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Vec<Interval> {
+        TransformedObject::new(self.object, (self.transform_at)(0.0)).ray_intervals(ray_origin, ray_direction)
+    }
+}
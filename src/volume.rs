@@ -0,0 +1,149 @@
+use raylib::prelude::Vector3;
+use rand::Rng;
+use crate::atmosphere::ray_box_range;
+use crate::noise::fbm;
+
+/// Density values on a regular 3D lattice, sampled trilinearly
+#[derive(Clone)]
+pub struct DensityGrid {
+    size: [usize; 3],
+    values: Vec<f32>, // En [0, 1], x varía más rápido, luego y, luego z
+}
+
+impl DensityGrid {
+    pub fn new(size_x: usize, size_y: usize, size_z: usize, values: Vec<f32>) -> Self {
+        assert_eq!(values.len(), size_x * size_y * size_z, "Density grid size mismatch");
+        DensityGrid {
+            size: [size_x, size_y, size_z],
+            values,
+        }
+    }
+
+    /// Loads an 8-bit raw volume (one byte per cell, x fastest), the usual
+    /// format of scanned and simulated density datasets
+    pub fn from_raw(path: &str, size_x: usize, size_y: usize, size_z: usize) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let expected = size_x * size_y * size_z;
+        if bytes.len() != expected {
+            return Err(format!("Invalid density grid {}: expected {} bytes, found {}", path, expected, bytes.len()));
+        }
+        let values = bytes.iter().map(|&b| b as f32 / 255.0).collect();
+        Ok(DensityGrid::new(size_x, size_y, size_z, values))
+    }
+
+    fn value(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.values[(z * self.size[1] + y) * self.size[0] + x]
+    }
+
+    // Interpolación trilineal; `uvw` en [0, 1] cubre la cuadrícula completa
+    fn sample(&self, uvw: [f32; 3]) -> f32 {
+        let mut base = [0; 3];
+        let mut next = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let position = (uvw[axis] * (self.size[axis] - 1) as f32).clamp(0.0, (self.size[axis] - 1) as f32);
+            base[axis] = position.floor() as usize;
+            next[axis] = (base[axis] + 1).min(self.size[axis] - 1);
+            fraction[axis] = position - base[axis] as f32;
+        }
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let [fx, fy, fz] = fraction;
+        let plane = |z: usize| {
+            lerp(
+                lerp(self.value(base[0], base[1], z), self.value(next[0], base[1], z), fx),
+                lerp(self.value(base[0], next[1], z), self.value(next[0], next[1], z), fx),
+                fy,
+            )
+        };
+        lerp(plane(base[2]), plane(next[2]), fz)
+    }
+}
+
+#[derive(Clone)]
+pub enum DensitySource {
+    /// Fractal noise shaped into a soft ellipsoid that fills the box
+    Noise { scale: f32, octaves: u32, seed: u32 },
+    /// Density grid stretched over the box
+    Grid(DensityGrid),
+}
+
+/// Box-bounded medium whose density varies from point to point (smoke,
+/// mist). Rendered with delta tracking to pick scattering events and ratio
+/// tracking to estimate transmittance.
+#[derive(Clone)]
+pub struct HeterogeneousVolume {
+    pub min: Vector3,
+    pub max: Vector3,
+    pub max_density: f32, // Mayorante: ningún punto supera esta densidad
+    pub albedo: Vector3,
+    pub source: DensitySource,
+}
+
+impl HeterogeneousVolume {
+    pub fn new(center: Vector3, size: Vector3, max_density: f32, albedo: Vector3, source: DensitySource) -> Self {
+        HeterogeneousVolume {
+            min: center - size * 0.5,
+            max: center + size * 0.5,
+            max_density,
+            albedo,
+            source,
+        }
+    }
+
+    pub fn density_at(&self, point: &Vector3) -> f32 {
+        let extent = self.max - self.min;
+        let uvw = [
+            (point.x - self.min.x) / extent.x,
+            (point.y - self.min.y) / extent.y,
+            (point.z - self.min.z) / extent.z,
+        ];
+        let value = match &self.source {
+            DensitySource::Noise { scale, octaves, seed } => {
+                // Elipsoide inscrito en la caja, con bordes deshilachados por el ruido
+                let radius_sq: f32 = uvw.iter().map(|c| (c * 2.0 - 1.0).powi(2)).sum();
+                let falloff = (1.0 - radius_sq).max(0.0);
+                falloff * (0.6 + fbm(*point * *scale, *octaves, *seed) * 1.5)
+            }
+            DensitySource::Grid(grid) => grid.sample(uvw),
+        };
+        value.clamp(0.0, 1.0) * self.max_density
+    }
+
+    pub fn ray_range(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, f32)> {
+        ray_box_range(ray_origin, ray_direction, &self.min, &self.max)
+    }
+
+    // Distancia hasta la siguiente colisión tentativa con el medio mayorante
+    fn free_flight(&self, rng: &mut impl Rng) -> f32 {
+        -(1.0 - rng.random::<f32>()).ln() / self.max_density
+    }
+
+    /// Delta tracking: distance of the first real collision in [t0, t1], if any
+    pub fn sample_collision(&self, ray_origin: &Vector3, ray_direction: &Vector3, t0: f32, t1: f32, rng: &mut impl Rng) -> Option<f32> {
+        let mut t = t0;
+        loop {
+            t += self.free_flight(rng);
+            if t >= t1 {
+                return None;
+            }
+            let density = self.density_at(&(*ray_origin + *ray_direction * t));
+            if rng.random::<f32>() * self.max_density < density {
+                return Some(t);
+            }
+        }
+    }
+
+    /// Ratio tracking: unbiased estimate of the transmittance over [t0, t1]
+    pub fn transmittance(&self, ray_origin: &Vector3, ray_direction: &Vector3, t0: f32, t1: f32, rng: &mut impl Rng) -> f32 {
+        let mut t = t0;
+        let mut transmittance = 1.0;
+        loop {
+            t += self.free_flight(rng);
+            if t >= t1 {
+                return transmittance;
+            }
+            let density = self.density_at(&(*ray_origin + *ray_direction * t));
+            transmittance *= 1.0 - density / self.max_density;
+        }
+    }
+}
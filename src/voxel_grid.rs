@@ -0,0 +1,407 @@
+use raylib::prelude::Vector3;
+use std::collections::HashMap;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+
+// Los vóxeles se agrupan en bloques de 8³; los bloques vacíos no ocupan memoria
+const BRICK_SIZE: usize = 8;
+const BRICK_VOLUME: usize = BRICK_SIZE * BRICK_SIZE * BRICK_SIZE;
+
+type Brick = [u8; BRICK_VOLUME];
+
+/// Sparse grid of cubic voxels, each storing a material id (0 = empty).
+/// Rays are traversed with a two-level 3D DDA (Amanatides–Woo): first over
+/// 8³ bricks, skipping the empty ones, then voxel by voxel inside each brick.
+#[derive(Clone)]
+pub struct VoxelGrid {
+    pub origin: Vector3, // Esquina mínima de la cuadrícula
+    pub voxel_size: f32,
+    size: [usize; 3],
+    brick_counts: [usize; 3],
+    bricks: Vec<Option<Box<Brick>>>,
+    palette: Vec<Material>, // El id n usa palette[n - 1]
+}
+
+// Recorrido de una cuadrícula de celdas cúbicas a lo largo de un rayo
+struct Dda {
+    cell: [i32; 3],
+    step: [i32; 3],
+    t_max: [f32; 3],   // Distancia hasta la siguiente frontera en cada eje
+    t_delta: [f32; 3], // Distancia entre fronteras consecutivas en cada eje
+}
+
+impl Dda {
+    // Celda que contiene el punto a distancia `t`, limitada a [lo, hi)
+    fn new(origin: [f32; 3], direction: [f32; 3], t: f32, cell_size: f32, lo: [i32; 3], hi: [i32; 3]) -> Self {
+        let mut dda = Dda { cell: [0; 3], step: [0; 3], t_max: [f32::INFINITY; 3], t_delta: [f32::INFINITY; 3] };
+        for axis in 0..3 {
+            let position = origin[axis] + direction[axis] * t;
+            dda.cell[axis] = ((position / cell_size).floor() as i32).clamp(lo[axis], hi[axis] - 1);
+            if direction[axis].abs() > 1e-8 {
+                dda.step[axis] = if direction[axis] > 0.0 { 1 } else { -1 };
+                let boundary = (dda.cell[axis] + (dda.step[axis] > 0) as i32) as f32 * cell_size;
+                dda.t_max[axis] = (boundary - origin[axis]) / direction[axis];
+                dda.t_delta[axis] = cell_size / direction[axis].abs();
+            }
+        }
+        dda
+    }
+
+    fn next_crossing(&self) -> (usize, f32) {
+        let axis = if self.t_max[0] < self.t_max[1] {
+            if self.t_max[0] < self.t_max[2] { 0 } else { 2 }
+        } else if self.t_max[1] < self.t_max[2] {
+            1
+        } else {
+            2
+        };
+        (axis, self.t_max[axis])
+    }
+
+    // Avanza a la celda vecina; devuelve el eje cruzado y la distancia del cruce
+    fn advance(&mut self) -> (usize, f32) {
+        let (axis, t) = self.next_crossing();
+        self.cell[axis] += self.step[axis];
+        self.t_max[axis] += self.t_delta[axis];
+        (axis, t)
+    }
+
+    fn inside(&self, lo: [i32; 3], hi: [i32; 3]) -> bool {
+        (0..3).all(|axis| self.cell[axis] >= lo[axis] && self.cell[axis] < hi[axis])
+    }
+}
+
+// Superficie encontrada al recorrer la cuadrícula (en unidades de vóxel)
+struct VoxelHit {
+    t: f32,
+    axis: usize,
+    id: u8,
+}
+
+impl VoxelGrid {
+    pub fn new(origin: Vector3, voxel_size: f32, size_x: usize, size_y: usize, size_z: usize) -> Self {
+        let size = [size_x, size_y, size_z];
+        let brick_counts = size.map(|n| n.div_ceil(BRICK_SIZE));
+        VoxelGrid {
+            origin,
+            voxel_size,
+            size,
+            brick_counts,
+            bricks: vec![None; brick_counts.iter().product()],
+            palette: Vec::new(),
+        }
+    }
+
+    /// Registers a material and returns the id to use with `set`
+    pub fn add_material(&mut self, material: Material) -> u8 {
+        assert!(self.palette.len() < u8::MAX as usize, "Voxel palette is full (255 materials)");
+        self.palette.push(material);
+        self.palette.len() as u8
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        if x >= self.size[0] || y >= self.size[1] || z >= self.size[2] {
+            return 0;
+        }
+        let (brick, offset) = self.locate(x, y, z);
+        self.bricks[brick].as_ref().map_or(0, |voxels| voxels[offset])
+    }
+
+    /// Sets a voxel; id 0 clears it. Cells outside the grid are ignored.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, id: u8) {
+        if x >= self.size[0] || y >= self.size[1] || z >= self.size[2] {
+            return;
+        }
+        let (brick, offset) = self.locate(x, y, z);
+        match &mut self.bricks[brick] {
+            Some(voxels) => voxels[offset] = id,
+            None if id != 0 => {
+                let mut voxels = Box::new([0; BRICK_VOLUME]);
+                voxels[offset] = id;
+                self.bricks[brick] = Some(voxels);
+            }
+            None => {}
+        }
+    }
+
+    /// Fills the inclusive box of cells from `min` to `max` with `id`
+    pub fn fill_box(&mut self, min: (usize, usize, usize), max: (usize, usize, usize), id: u8) {
+        for z in min.2..=max.2 {
+            for y in min.1..=max.1 {
+                for x in min.0..=max.0 {
+                    self.set(x, y, z, id);
+                }
+            }
+        }
+    }
+
+    /// Loads the first model of a MagicaVoxel `.vox` file. Every palette
+    /// colour in use becomes a copy of `base_material` with that diffuse colour
+    /// (and no texture, so the colour shows). MagicaVoxel is Z-up; the model is
+    /// turned so its up axis is +Y, with the front view facing +Z.
+    pub fn from_vox(path: &str, origin: Vector3, voxel_size: f32, base_material: &Material) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let vox = parse_vox(&bytes).map_err(|e| format!("Invalid .vox file {}: {}", path, e))?;
+
+        let [size_x, size_y, size_z] = vox.size;
+        let mut grid = VoxelGrid::new(origin, voxel_size, size_x, size_z, size_y);
+
+        // Índice de paleta .vox -> id de material de la cuadrícula
+        let mut ids: HashMap<u8, u8> = HashMap::new();
+        for (x, y, z, color_index) in vox.voxels {
+            let id = match ids.get(&color_index) {
+                Some(&id) => id,
+                None => {
+                    let [r, g, b] = vox.palette[color_index as usize];
+                    let mut material = base_material.clone();
+                    material.diffuse = Vector3::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+                    material.texture_id = None;
+                    let id = grid.add_material(material);
+                    ids.insert(color_index, id);
+                    id
+                }
+            };
+            grid.set(x as usize, z as usize, size_y - 1 - y as usize, id);
+        }
+
+        Ok(grid)
+    }
+
+    fn locate(&self, x: usize, y: usize, z: usize) -> (usize, usize) {
+        let [bx, by, bz] = [x / BRICK_SIZE, y / BRICK_SIZE, z / BRICK_SIZE];
+        let [lx, ly, lz] = [x % BRICK_SIZE, y % BRICK_SIZE, z % BRICK_SIZE];
+        let brick = (bz * self.brick_counts[1] + by) * self.brick_counts[0] + bx;
+        let offset = (lz * BRICK_SIZE + ly) * BRICK_SIZE + lx;
+        (brick, offset)
+    }
+
+    // Rango [t0, t1] del rayo dentro de la caja de la cuadrícula y eje de entrada y salida
+    fn grid_range(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<(f32, usize, f32, usize)> {
+        let (mut t0, mut t1) = (f32::NEG_INFINITY, f32::INFINITY);
+        let (mut enter_axis, mut exit_axis) = (0, 0);
+        for axis in 0..3 {
+            let inv = 1.0 / direction[axis];
+            let near = (0.0 - origin[axis]) * inv;
+            let far = (self.size[axis] as f32 - origin[axis]) * inv;
+            let (near, far) = if near < far { (near, far) } else { (far, near) };
+            // Rayo paralelo a las caras de este eje
+            if near.is_nan() || far.is_nan() {
+                if origin[axis] < 0.0 || origin[axis] > self.size[axis] as f32 {
+                    return None;
+                }
+                continue;
+            }
+            if near > t0 {
+                t0 = near;
+                enter_axis = axis;
+            }
+            if far < t1 {
+                t1 = far;
+                exit_axis = axis;
+            }
+        }
+        if t0 > t1 || t1 < 0.0 {
+            return None;
+        }
+        Some((t0.max(0.0), enter_axis, t1, exit_axis))
+    }
+
+    // Primera celda del rayo que cumple `is_target`, recorriendo solo los bloques necesarios
+    fn traverse(&self, origin: [f32; 3], direction: [f32; 3], inside: bool) -> Option<VoxelHit> {
+        let (t_start, enter_axis, t_end, exit_axis) = self.grid_range(origin, direction)?;
+        let is_target = |id: u8| if inside { id == 0 } else { id != 0 };
+        let brick_hi = self.brick_counts.map(|n| n as i32);
+        let size_hi = self.size.map(|n| n as i32);
+
+        let mut bricks = Dda::new(origin, direction, t_start, BRICK_SIZE as f32, [0; 3], brick_hi);
+        let (mut t, mut axis) = (t_start, enter_axis);
+        let mut last_id = 0;
+
+        while t <= t_end && bricks.inside([0; 3], brick_hi) {
+            let [bx, by, bz] = bricks.cell.map(|c| c as usize);
+            let brick = (bz * self.brick_counts[1] + by) * self.brick_counts[0] + bx;
+
+            match &self.bricks[brick] {
+                // Bloque vacío: fuera de un sólido se salta entero, dentro de él es la salida
+                None if inside => return Some(VoxelHit { t, axis, id: last_id }),
+                None => {}
+                Some(voxels) => {
+                    let lo = bricks.cell.map(|c| c * BRICK_SIZE as i32);
+                    let hi = [0, 1, 2].map(|a| (lo[a] + BRICK_SIZE as i32).min(size_hi[a]));
+                    let mut cells = Dda::new(origin, direction, t, 1.0, lo, hi);
+                    let (mut cell_t, mut cell_axis) = (t, axis);
+
+                    while cells.inside(lo, hi) {
+                        let [lx, ly, lz] = [0, 1, 2].map(|a| (cells.cell[a] - lo[a]) as usize);
+                        let id = voxels[(lz * BRICK_SIZE + ly) * BRICK_SIZE + lx];
+                        if is_target(id) {
+                            let id = if inside { last_id } else { id };
+                            return Some(VoxelHit { t: cell_t, axis: cell_axis, id });
+                        }
+                        if id != 0 {
+                            last_id = id;
+                        }
+                        (cell_axis, cell_t) = cells.advance();
+                    }
+                }
+            }
+
+            (axis, t) = bricks.advance();
+        }
+
+        // Un rayo que nace dentro sale del sólido al salir de la cuadrícula
+        if inside {
+            return Some(VoxelHit { t: t_end, axis: exit_axis, id: last_id });
+        }
+        None
+    }
+}
+
+impl RayIntersect for VoxelGrid {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        // Espacio local: un vóxel mide 1, así que t_local = t / voxel_size
+        let local = (*ray_origin - self.origin) / self.voxel_size;
+        let origin = [local.x, local.y, local.z];
+        let direction = [ray_direction.x, ray_direction.y, ray_direction.z];
+
+        // Si el rayo nace dentro de un vóxel sólido (refracción) se busca la salida
+        let start = origin.map(|c| c.floor());
+        let inside = start.iter().all(|&c| c >= 0.0)
+            && self.get(start[0] as usize, start[1] as usize, start[2] as usize) != 0;
+
+        let Some(hit) = self.traverse(origin, direction, inside) else {
+            return Intersect::empty();
+        };
+        if hit.t <= 0.0 || hit.id == 0 {
+            return Intersect::empty();
+        }
+
+        let distance = hit.t * self.voxel_size;
+        let point = *ray_origin + *ray_direction * distance;
+
+        // La normal apunta contra el rayo al entrar y a favor de él al salir
+        let sign = if (direction[hit.axis] > 0.0) == inside { 1.0 } else { -1.0 };
+        let mut normal = [0.0; 3];
+        normal[hit.axis] = sign;
+        let normal = Vector3::new(normal[0], normal[1], normal[2]);
+
+        // Cada cara del vóxel lleva la textura completa, como en los cubos
+        let local_point = (point - self.origin) / self.voxel_size;
+        let fraction = |value: f32| value - value.floor();
+        let (u, v, tangent) = match hit.axis {
+            0 => (fraction(local_point.z), fraction(local_point.y), Vector3::new(0.0, 0.0, 1.0)),
+            1 => (fraction(local_point.x), fraction(local_point.z), Vector3::new(1.0, 0.0, 0.0)),
+            _ => (fraction(local_point.x), fraction(local_point.y), Vector3::new(1.0, 0.0, 0.0)),
+        };
+
+        let material = self.palette[hit.id as usize - 1].clone();
+        Intersect::new(point, normal, distance, material, u, 1.0 - v).with_tangent(tangent)
+    }
+}
+
+// Contenido de un modelo .vox
+struct VoxModel {
+    size: [usize; 3],
+    voxels: Vec<(u8, u8, u8, u8)>, // x, y, z, índice de paleta
+    palette: [[u8; 3]; 256],
+}
+
+// Lector de bytes little-endian con comprobación de límites
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len())
+            .ok_or("unexpected end of file")?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+fn parse_vox(bytes: &[u8]) -> Result<VoxModel, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != b"VOX " {
+        return Err("missing 'VOX ' header".to_string());
+    }
+    reader.u32()?; // Versión
+
+    if reader.take(4)? != b"MAIN" {
+        return Err("missing MAIN chunk".to_string());
+    }
+    let main_content = reader.u32()? as usize;
+    reader.take(4)?; // Tamaño de los hijos: se leen hasta el final
+    reader.take(main_content)?;
+
+    let mut size = None;
+    let mut voxels = None;
+    let mut palette = default_vox_palette();
+
+    while reader.position < bytes.len() {
+        let id = reader.take(4)?;
+        let content_size = reader.u32()? as usize;
+        let children_size = reader.u32()? as usize;
+        let mut content = Reader { bytes: reader.take(content_size)?, position: 0 };
+        reader.take(children_size)?;
+
+        match id {
+            // Solo se importa el primer modelo del archivo
+            b"SIZE" if size.is_none() => {
+                size = Some([content.u32()? as usize, content.u32()? as usize, content.u32()? as usize]);
+            }
+            b"XYZI" if voxels.is_none() => {
+                let count = content.u32()? as usize;
+                let data = content.take(count.checked_mul(4).ok_or("voxel count overflow")?)?;
+                voxels = Some(data.chunks_exact(4).map(|v| (v[0], v[1], v[2], v[3])).collect::<Vec<_>>());
+            }
+            // El color i del chunk corresponde al índice i + 1
+            b"RGBA" => {
+                let data = content.take(256 * 4)?;
+                for (i, color) in data.chunks_exact(4).take(255).enumerate() {
+                    palette[i + 1] = [color[0], color[1], color[2]];
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let size = size.ok_or("missing SIZE chunk")?;
+    let voxels: Vec<_> = voxels.ok_or("missing XYZI chunk")?
+        .into_iter()
+        .filter(|&(x, y, z, color)| color != 0 && (x as usize) < size[0] && (y as usize) < size[1] && (z as usize) < size[2])
+        .collect();
+    Ok(VoxModel { size, voxels, palette })
+}
+
+// Paleta por defecto de MagicaVoxel: cubo de color 6x6x6 seguido de rampas de rojo, verde, azul y gris
+fn default_vox_palette() -> [[u8; 3]; 256] {
+    let mut palette = [[0; 3]; 256];
+    let levels = [0xff, 0xcc, 0x99, 0x66, 0x33, 0x00];
+    let mut index = 1;
+    for r in levels {
+        for g in levels {
+            for b in levels {
+                if index < 216 {
+                    palette[index] = [r, g, b];
+                    index += 1;
+                }
+            }
+        }
+    }
+    let ramp = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+    for (i, level) in ramp.into_iter().enumerate() {
+        palette[216 + i] = [level, 0, 0];
+        palette[226 + i] = [0, level, 0];
+        palette[236 + i] = [0, 0, level];
+        palette[246 + i] = [level, level, level];
+    }
+    palette
+}